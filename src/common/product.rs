@@ -1,5 +1,6 @@
 /// Represents a LIFX product.
 #[allow(missing_docs)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Product {
     Original1000,
     Color650,
@@ -21,9 +22,40 @@ pub enum Product {
     LIFXMiniWhite,
     LIFXGU10,
     LIFXTile,
+    LIFXClean,
 }
 
 impl Product {
+    /// Finds the product with the given product ID, if it is known.
+    pub fn from_pid(pid: u32) -> Option<Self> {
+        use self::Product::*;
+        [
+            Original1000,
+            Color650,
+            White800LV,
+            White800HV,
+            White900BR30,
+            Color1000BR30,
+            Color1000,
+            LIFXA19,
+            LIFXBR30,
+            LIFXPlusA19,
+            LIFXPlusBR30,
+            LIFXZ,
+            LIFXZ2,
+            LIFXDownlight,
+            LIFXBeam,
+            LIFXMini,
+            LIFXMiniDayDusk,
+            LIFXMiniWhite,
+            LIFXGU10,
+            LIFXTile,
+            LIFXClean,
+        ]
+        .iter()
+        .copied()
+        .find(|product| product.pid() == pid)
+    }
     /// Gives the vendor ID of this product.
     pub fn vid(&self) -> u32 {
         1
@@ -52,6 +84,7 @@ impl Product {
             LIFXMiniWhite => 51,
             LIFXGU10 => 52,
             LIFXTile => 55,
+            LIFXClean => 90,
         }
     }
     /// Gives the consumer-friendly name of this product.
@@ -78,6 +111,7 @@ impl Product {
             LIFXMiniWhite => "LIFX Mini White",
             LIFXGU10 => "LIFX GU10",
             LIFXTile => "LIFX Tile",
+            LIFXClean => "LIFX Clean",
         }
     }
     /// Indicates whether this product has color support.
@@ -96,6 +130,10 @@ impl Product {
             _ => false,
         }
     }
+    /// Indicates whether this product has HEV (germicidal "clean") support.
+    pub fn hev(&self) -> bool {
+        *self == Self::LIFXClean
+    }
     /// Indicates whether this product supports multizoning.
     pub fn multizone(&self) -> bool {
         use self::Product::*;
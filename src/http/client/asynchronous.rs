@@ -0,0 +1,303 @@
+//! Asynchronous counterparts to [`Request::send`](struct.Request.html#method.send) and
+//! [`Send`](trait.Send.html), enabled by the `async` feature.
+
+use std::time::{Duration, Instant};
+
+use futures::{
+    future::{self, Loop},
+    stream, Future, IntoFuture, Stream,
+};
+use reqwest::{
+    header::{HeaderMap, HeaderValue, CONTENT_TYPE},
+    r#async::{Client as AsyncClient, Response as AsyncResponse},
+    Method,
+};
+use serde::Serialize;
+use tokio_timer::Delay;
+
+use crate::http::client::{
+    backoff_delay, is_bad_request, rate_limit_reset, status_error, wait_until, ApiErrorBody,
+    AsRequest, BreathePayload, BreatheSequence, Error, Request, Retry, RATE_LIMIT_BACKOFF,
+};
+use crate::http::selector::Select;
+
+/// The result type for asynchronous requests: a future resolving to the response.
+///
+/// These are [futures 0.1](https://docs.rs/futures/0.1) futures, as used by reqwest's
+/// asynchronous client, and must be run on a Tokio (0.1) runtime.
+pub type AsyncClientResult =
+    Box<dyn Future<Item = AsyncResponse, Error = Error> + ::std::marker::Send>;
+
+/// The result type for asynchronous sequences of requests: a future resolving to the responses,
+/// in order.
+pub type AsyncSequenceResult =
+    Box<dyn Future<Item = Vec<AsyncResponse>, Error = Error> + ::std::marker::Send>;
+
+/// A request which has been prepared, and is sent when called.
+type Deferred = Box<dyn FnOnce() -> AsyncClientResult + ::std::marker::Send>;
+
+/// Everything needed to (re-)send a request without borrowing from it.
+#[derive(Clone)]
+struct Attempt {
+    client: AsyncClient,
+    method: Method,
+    url: String,
+    token: String,
+    headers: HeaderMap,
+    body: Vec<u8>,
+}
+
+impl Attempt {
+    fn send(&self) -> impl Future<Item = AsyncResponse, Error = Error> {
+        self.client
+            .request(self.method.clone(), &self.url)
+            .bearer_auth(&self.token)
+            .header(CONTENT_TYPE, HeaderValue::from_static("application/json"))
+            .body(self.body.clone())
+            .headers(self.headers.clone())
+            .send()
+            .map_err(Error::from)
+            .and_then(|mut response| {
+                if is_bad_request(response.status()) {
+                    let details = response.json::<ApiErrorBody>().then(|body| {
+                        let details = body.map(|body| body.errors).unwrap_or_default();
+                        Err(Error::BadRequest(details))
+                    });
+                    return future::Either::A(details);
+                }
+                let reset = rate_limit_reset(response.headers());
+                future::Either::B(
+                    response
+                        .error_for_status()
+                        .map_err(|e| status_error(e, reset))
+                        .into_future(),
+                )
+            })
+    }
+}
+
+impl<'a, S> Request<'a, S>
+where
+    S: Serialize,
+{
+    /// Sends the request without blocking, returning a future which resolves to the response.
+    ///
+    /// Retries behave exactly as they do for [`send`](#method.send), except that waiting between
+    /// attempts uses a timer instead of putting the thread to sleep.
+    ///
+    /// ## Notes
+    /// Unlike [`send`](#method.send), this bypasses the client's rate-limit tracker: responses
+    /// aren't recorded (so [`Client::rate_limit`](struct.Client.html#method.rate_limit) isn't
+    /// updated) and the [rate-limit policy](struct.ClientBuilder.html#method.rate_limit_policy)
+    /// isn't applied before sending. The client's light cache isn't consulted or updated either.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() {
+    /// use futures::Future;
+    /// let client = Client::new("foo");
+    /// let request = client
+    ///     .select(Selector::All)
+    ///     .set_state()
+    ///     .color(Color::Red)
+    ///     .send_async()
+    ///     .map(|response| println!("Status: {}", response.status()))
+    ///     .map_err(|e| eprintln!("Error: {}", e));
+    /// // Spawn `request` onto a Tokio runtime, e.g. with `tokio::run(request)`.
+    /// # }
+    /// ```
+    pub fn send_async(&self) -> AsyncClientResult {
+        (self.defer())()
+    }
+    /// Prepares the request for sending without sending it yet.
+    fn defer(&self) -> Deferred {
+        if self.guarded {
+            return Box::new(|| -> AsyncClientResult {
+                Box::new(future::err(Error::UnconfirmedAll))
+            });
+        }
+        let client = self.client.async_client.clone();
+        let url = self.url();
+        let body = match serde_json::to_vec(&self.body) {
+            Ok(body) => body,
+            Err(_) => {
+                // Let reqwest report the failure, as it does for synchronous requests.
+                let err = client
+                    .request(self.method.clone(), &url)
+                    .json(&self.body)
+                    .build();
+                let err = err
+                    .err()
+                    .map_or_else(|| Error::BadRequest(Vec::new()), Error::from);
+                return Box::new(move || -> AsyncClientResult { Box::new(future::err(err)) });
+            }
+        };
+        let attempt = Attempt {
+            client,
+            method: self.method.clone(),
+            url,
+            token: self.client.token.clone(),
+            headers: self.headers.clone(),
+            body,
+        };
+        let attempts = self.attempts.get();
+        let base = self.backoff;
+        Box::new(move || retrying(attempt, attempts, base))
+    }
+}
+
+/// Sends the attempt, retrying (up to the given total number of attempts, backing off from the
+/// given base delay if any) as [`Request::send`](struct.Request.html#method.send) would.
+fn retrying(attempt: Attempt, attempts: u8, base: Option<Duration>) -> AsyncClientResult {
+    let retries = future::loop_fn((1, RATE_LIMIT_BACKOFF), move |(count, backoff)| {
+        attempt
+            .send()
+            .then(move |result| {
+                let e = match result {
+                    Ok(response) => return Ok(Loop::Break(response)),
+                    Err(e) => e,
+                };
+                let (wait, backoff): (Duration, Duration) = match e {
+                    _ if count >= attempts => return Err(e),
+                    Error::RateLimited(Some(t)) => {
+                        // Wait until we're allowed to try again.
+                        (wait_until(t), backoff)
+                    }
+                    Error::RateLimited(None) => {
+                        // We don't know how long to wait, so back off exponentially.
+                        (backoff, backoff * 2)
+                    }
+                    _ if e.is_client_error() => return Err(e),
+                    _ => match base {
+                        Some(base) => (backoff_delay(base, u32::from(count - 1)), backoff),
+                        None => (Duration::from_secs(0), backoff),
+                    },
+                };
+                Ok(Loop::Continue((wait, count + 1, backoff)))
+            })
+            .and_then(|step| match step {
+                Loop::Break(response) => future::Either::A(future::ok(Loop::Break(response))),
+                Loop::Continue((wait, count, backoff)) => future::Either::B(
+                    // A failed timer only cuts the wait short.
+                    Delay::new(Instant::now() + wait)
+                        .then(move |_| Ok(Loop::Continue((count, backoff)))),
+                ),
+            })
+    });
+    Box::new(retries)
+}
+
+/// Prepares a configurable request for sending without sending it yet, as
+/// [`SendAsync::send_async`](trait.SendAsync.html#tymethod.send_async) would send it.
+fn defer<T, S>(request: &T) -> Deferred
+where
+    T: AsRequest<S> + Retry,
+    S: Serialize,
+{
+    // Preflights run here, synchronously, so any which make requests block the caller.
+    match request.preflight() {
+        Ok(Some(path)) => {
+            let mut request = crate::http::Send::request(request);
+            request.path = path;
+            request.defer()
+        }
+        Ok(None) => Box::new(|| -> AsyncClientResult {
+            // Nothing needed sending, so report an empty success.
+            let mut response = http::Response::new("");
+            *response.status_mut() = http::StatusCode::NO_CONTENT;
+            Box::new(future::ok(response.into()))
+        }),
+        Err(e) => Box::new(move || -> AsyncClientResult { Box::new(future::err(e)) }),
+    }
+}
+
+/// Asynchronous counterpart to [`Send`](trait.Send.html) for configurable requests.
+///
+/// ## Example
+/// ```
+/// use lifxi::http::prelude::*;
+/// # fn run() {
+/// use futures::Future;
+/// let client = Client::new("foo");
+/// let office = client.select(Selector::Label("Office".to_string()));
+/// let request = office
+///     .breathe(Color::Blue)
+///     .cycles(3)
+///     .send_async()
+///     .map(|_| ())
+///     .map_err(|e| eprintln!("Error: {}", e));
+/// // Spawn `request` onto a Tokio runtime, e.g. with `tokio::run(request)`.
+/// # }
+/// ```
+pub trait SendAsync<S> {
+    /// Sends the request without blocking.
+    ///
+    /// This method delegates to `Request::send_async`, so take a look at
+    /// [that documentation](struct.Request.html#method.send_async) for more information.
+    ///
+    /// ## Notes
+    /// Any checks made before sending (such as effect validation) run immediately, on the calling
+    /// thread, before the future is returned. Most of these are local, but
+    /// [`SetState::if_connected`](struct.SetState.html#method.if_connected) lists the selected
+    /// lights first, which is a blocking request; avoid it when calling this from within a
+    /// running executor.
+    fn send_async(&self) -> AsyncClientResult;
+}
+
+impl<T, S> SendAsync<S> for T
+where
+    T: AsRequest<S> + Retry,
+    S: Serialize,
+{
+    /// Delegates to [`Request::send_async`](struct.Request.html#method.send_async).
+    fn send_async(&self) -> AsyncClientResult {
+        defer(self)()
+    }
+}
+
+impl<'a, T: Select> BreatheSequence<'a, T> {
+    /// Sends each step in turn without blocking, waiting for it to finish before sending the next
+    /// (as with [`send`](#method.send)).
+    ///
+    /// The future stops at (and resolves to) the first error.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() {
+    /// use futures::Future;
+    /// let client = Client::new("foo");
+    /// let selected = client.select(Selector::All);
+    /// let request = selected
+    ///     .breathe_sequence(
+    ///         vec![Color::Red, Color::Blue],
+    ///         ::std::time::Duration::new(5, 0),
+    ///     )
+    ///     .send_async()
+    ///     .map(|responses| println!("Sent {} steps.", responses.len()))
+    ///     .map_err(|e| eprintln!("Error: {}", e));
+    /// // Spawn `request` onto a Tokio runtime, e.g. with `tokio::run(request)`.
+    /// # }
+    /// ```
+    pub fn send_async(&self) -> AsyncSequenceResult {
+        let period = self.period;
+        let steps = self
+            .steps()
+            .iter()
+            .map(defer::<_, BreathePayload<'a, T>>)
+            .collect::<Vec<_>>();
+        let responses = stream::iter_ok::<_, Error>(steps.into_iter().enumerate())
+            .and_then(move |(i, step)| {
+                let wait = if i == 0 {
+                    Duration::from_secs(0)
+                } else {
+                    period
+                };
+                // A failed timer only cuts the wait short.
+                Delay::new(Instant::now() + wait).then(move |_| step())
+            })
+            .collect();
+        Box::new(responses)
+    }
+}
@@ -1,12 +1,122 @@
+use std::fmt;
 use std::num::NonZeroU8;
+use std::time::Duration as StdDuration;
 
 use crate::http::{
-    client::{unity, AsRequest, Attempts, Client, Selected},
+    client::{AsRequest, Attempts, Client, Direction, Error, Retries, Selected, Send},
     selector::Select,
-    state::{Color, Duration},
+    state::{Color, Duration, Level},
 };
 use reqwest::Method;
 
+/// The default limit on the total effect duration (period × cycles) accepted by local validation.
+///
+/// The API rejects overly long animations with a generic bad request error; it doesn't publish
+/// an exact limit, so this is a conservative bound. It may be changed per request with
+/// [`Breathe::max_duration`](struct.Breathe.html#method.max_duration) (or
+/// [`Pulse::max_duration`](struct.Pulse.html#method.max_duration)).
+pub const MAX_EFFECT_DURATION: StdDuration = StdDuration::from_secs(60 * 60);
+
+/// The largest morph palette accepted by local validation.
+///
+/// Tiles receive the palette in the LAN protocol's tile effect message, which has room for at
+/// most 16 colors.
+pub const MAX_MORPH_PALETTE: usize = 16;
+
+/// Represents a (local) effect validation error.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EffectValidationError {
+    /// The total duration of the effect (period × cycles) exceeds the limit, which is
+    /// [`MAX_EFFECT_DURATION`](constant.MAX_EFFECT_DURATION.html) unless set with
+    /// [`Breathe::max_duration`](struct.Breathe.html#method.max_duration) (or
+    /// [`Pulse::max_duration`](struct.Pulse.html#method.max_duration)). The total duration and
+    /// the limit are returned, in that order.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::{prelude::*, EffectValidationError};
+    /// use std::time::Duration;
+    /// let client = Client::new("foo");
+    /// let selected = client.select(Selector::All);
+    /// let mut breathe = selected.breathe(Color::Red);
+    /// breathe.period(Duration::new(60, 0)).cycles(100);
+    /// assert_eq!(
+    ///     breathe.validate(),
+    ///     Err(EffectValidationError::DurationTooLong(
+    ///         Duration::new(6000, 0),
+    ///         Duration::new(3600, 0)
+    ///     ))
+    /// );
+    /// breathe.max_duration(Duration::new(2 * 3600, 0));
+    /// assert_eq!(breathe.validate(), Ok(()));
+    /// ```
+    DurationTooLong(StdDuration, StdDuration),
+    /// A morph palette was empty.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::{prelude::*, EffectValidationError};
+    /// let client = Client::new("foo");
+    /// let selected = client.select(Selector::All);
+    /// let mut morph = selected.morph();
+    /// assert_eq!(morph.palette(vec![]).validate(), Err(EffectValidationError::EmptyPalette));
+    /// ```
+    EmptyPalette,
+    /// A morph palette had more than [`MAX_MORPH_PALETTE`](constant.MAX_MORPH_PALETTE.html)
+    /// colors. The size of the palette is returned.
+    PaletteTooLarge(usize),
+    /// The period of a move effect was zero.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::{prelude::*, EffectValidationError};
+    /// let client = Client::new("foo");
+    /// let selected = client.select(Selector::All);
+    /// let mut effect = selected.move_effect();
+    /// effect.period(::std::time::Duration::new(0, 0));
+    /// assert_eq!(effect.validate(), Err(EffectValidationError::ZeroPeriod));
+    /// ```
+    ZeroPeriod,
+}
+
+impl fmt::Display for EffectValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::DurationTooLong(d, max) => write!(
+                f,
+                "Total effect duration {}s exceeds limit ({}s); reduce the period or cycle count.",
+                d.as_secs(),
+                max.as_secs()
+            ),
+            Self::EmptyPalette => write!(f, "Morph palette is empty."),
+            Self::PaletteTooLarge(n) => write!(
+                f,
+                "Morph palette has {} colors; at most {} are allowed.",
+                n, MAX_MORPH_PALETTE
+            ),
+            Self::ZeroPeriod => write!(f, "Effect period must be greater than zero."),
+        }
+    }
+}
+
+impl ::std::error::Error for EffectValidationError {}
+
+/// Checks the total duration of an effect against the given limit, using the API defaults (a
+/// one-second period and a single cycle) where unspecified.
+fn validate_duration(
+    period: Option<Duration>,
+    cycles: Option<u16>,
+    max: StdDuration,
+) -> Result<(), EffectValidationError> {
+    let period = period.map_or(StdDuration::from_secs(1), |p| p.0);
+    let total = period * u32::from(cycles.unwrap_or(1));
+    if total > max {
+        Err(EffectValidationError::DurationTooLong(total, max))
+    } else {
+        Ok(())
+    }
+}
+
 #[derive(Clone, Serialize)]
 #[doc(hidden)]
 /// The message constructed by the `Breathe` request builder.
@@ -65,7 +175,8 @@ impl<'a, T: Select> BreathePayload<'a, T> {
 pub struct Breathe<'a, T: Select> {
     pub(crate) parent: &'a Selected<'a, T>,
     inner: BreathePayload<'a, T>,
-    attempts: Option<NonZeroU8>,
+    max_duration: StdDuration,
+    retries: Retries,
 }
 
 impl<'a, T: Select> Breathe<'a, T> {
@@ -73,7 +184,8 @@ impl<'a, T: Select> Breathe<'a, T> {
         Self {
             parent,
             inner: BreathePayload::new(&parent.selector, color),
-            attempts: None,
+            max_duration: MAX_EFFECT_DURATION,
+            retries: Retries::default(),
         }
     }
     /// Sets the starting color.
@@ -97,6 +209,29 @@ impl<'a, T: Select> Breathe<'a, T> {
         self.inner.from = Some(color);
         self
     }
+    /// Starts the effect from the current color of the bulb(s), clearing any starting color set
+    /// with [`from`](#method.from).
+    ///
+    /// This is the default behavior (the API uses the current color when no starting color is
+    /// sent); this method just makes that intent explicit.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() {
+    /// let secret = "foo";
+    /// let client = Client::new(secret);
+    /// let result = client
+    ///     .select(Selector::All)
+    ///     .breathe(Color::Green)
+    ///     .from_current()
+    ///     .send();
+    /// # }
+    /// ```
+    pub fn from_current(&mut self) -> &'_ mut Self {
+        self.inner.from = None;
+        self
+    }
     /// Sets the animation duration.
     ///
     /// ## Example
@@ -188,15 +323,47 @@ impl<'a, T: Select> Breathe<'a, T> {
     ///     .send();
     /// # }
     /// ```
-    pub fn peak(&mut self, frac: f32) -> &'_ mut Self {
-        self.inner.peak = Some(frac);
+    pub fn peak<L: Into<Level>>(&mut self, frac: L) -> &'_ mut Self {
+        self.inner.peak = Some(frac.into().get());
         self
     }
+    /// Sets a limit on the total duration of the effect (period × cycles), checked locally.
+    ///
+    /// The API rejects overly long effects with a generic bad request error, but doesn't
+    /// document its limit, so a conservative one
+    /// ([`MAX_EFFECT_DURATION`](constant.MAX_EFFECT_DURATION.html)) is checked by default to fail
+    /// early with a descriptive error instead. This overrides that limit.
+    pub fn max_duration(&mut self, max: StdDuration) -> &'_ mut Self {
+        self.max_duration = max;
+        self
+    }
+    /// Checks the effect locally, ensuring that its total duration doesn't exceed the
+    /// [limit](#method.max_duration).
+    ///
+    /// This is also checked before sending, failing with
+    /// [`Error::InvalidEffect`](enum.Error.html#variant.InvalidEffect) without sending anything.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// use std::time::Duration;
+    /// let client = Client::new("foo");
+    /// let selected = client.select(Selector::All);
+    /// let mut breathe = selected.breathe(Color::Green);
+    /// assert!(breathe.cycles(10).validate().is_ok());
+    /// assert!(breathe.period(Duration::new(3600, 0)).validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<(), EffectValidationError> {
+        validate_duration(self.inner.period, self.inner.cycles, self.max_duration)
+    }
 }
 
 impl<'a, T: Select> Attempts for Breathe<'a, T> {
     fn set_attempts(&mut self, attempts: NonZeroU8) {
-        self.attempts = Some(attempts);
+        self.retries.attempts = Some(attempts);
+    }
+    fn set_backoff(&mut self, base: StdDuration) {
+        self.retries.backoff = Some(base);
     }
 }
 
@@ -214,7 +381,110 @@ impl<'a, T: Select> AsRequest<BreathePayload<'a, T>> for Breathe<'a, T> {
         &self.inner
     }
     fn attempts(&self) -> NonZeroU8 {
-        self.attempts.unwrap_or_else(unity)
+        self.retries.attempts()
+    }
+    fn retry_backoff(&self) -> Option<StdDuration> {
+        self.retries.backoff
+    }
+    fn guarded(&self) -> bool {
+        self.parent.guarded()
+    }
+    fn preflight(&self) -> Result<Option<String>, Error> {
+        self.validate().map_err(Error::InvalidEffect)?;
+        Ok(Some(self.path()))
+    }
+}
+
+/// Breathes through a sequence of colors, one after another, wrapping back around to the first.
+///
+/// The firmware's breathe effect only knows two colors, so this is a client-side composition: for
+/// colors A, B, and C, single-cycle breathes from A to B, B to C, and C back to A are sent in turn,
+/// each peaking at its end and persisting so that the next step starts where the last one left
+/// off.
+///
+/// ## Timing
+/// [`send`](#method.send) blocks, waiting one period after each request before sending the next.
+/// Request latency therefore adds a short pause between steps; an effect started while another
+/// is still running replaces it, so steps never overlap. Sequences of fewer than two colors send
+/// nothing.
+///
+/// ## Example
+/// ```
+/// use lifxi::http::prelude::*;
+/// # fn run() {
+/// let secret = "foo";
+/// let client = Client::new(secret);
+/// let result = client
+///     .select(Selector::All)
+///     .breathe_sequence(
+///         vec![Color::Red, Color::Purple, Color::Blue],
+///         ::std::time::Duration::new(5, 0),
+///     )
+///     .power(true)
+///     .send();
+/// # }
+/// ```
+pub struct BreatheSequence<'a, T: Select> {
+    parent: &'a Selected<'a, T>,
+    colors: Vec<Color>,
+    pub(crate) period: StdDuration,
+    power_on: Option<bool>,
+}
+
+impl<'a, T: Select> BreatheSequence<'a, T> {
+    pub(crate) fn new(
+        parent: &'a Selected<'a, T>,
+        colors: Vec<Color>,
+        period: StdDuration,
+    ) -> Self {
+        Self {
+            parent,
+            colors,
+            period,
+            power_on: None,
+        }
+    }
+    /// Sets whether to power on the light if currently off.
+    pub fn power(&mut self, force: bool) -> &'_ mut Self {
+        self.power_on = Some(force);
+        self
+    }
+    /// Builds the individual breathe requests making up the sequence, in order.
+    pub fn steps(&self) -> Vec<Breathe<'a, T>> {
+        if self.colors.len() < 2 {
+            return Vec::new();
+        }
+        let next = self.colors.iter().cycle().skip(1);
+        self.colors
+            .iter()
+            .zip(next)
+            .map(|(from, to)| {
+                let mut step = Breathe::new(self.parent, to.clone());
+                step.from(from.clone())
+                    .period(self.period)
+                    .cycles(1)
+                    .peak(1.0)
+                    .persist(true);
+                if let Some(power) = self.power_on {
+                    step.power(power);
+                }
+                step
+            })
+            .collect()
+    }
+    /// Sends each step in turn, waiting for it to finish before sending the next.
+    ///
+    /// Stops at (and returns) the first error.
+    pub fn send(&self) -> Result<Vec<reqwest::Response>, Error> {
+        let steps = self.steps();
+        let mut responses = Vec::with_capacity(steps.len());
+        for (i, step) in steps.iter().enumerate() {
+            responses.push(step.send()?);
+            if i + 1 < steps.len() {
+                ::std::thread::sleep(self.period);
+            }
+        }
+        Ok(responses)
     }
 }
 
@@ -254,7 +524,8 @@ impl<'a, T: Select> PulsePayload<'a, T> {
 pub struct Pulse<'a, T: Select> {
     parent: &'a Selected<'a, T>,
     inner: PulsePayload<'a, T>,
-    attempts: Option<NonZeroU8>,
+    max_duration: StdDuration,
+    retries: Retries,
 }
 
 impl<'a, T: Select> Pulse<'a, T> {
@@ -262,7 +533,8 @@ impl<'a, T: Select> Pulse<'a, T> {
         Self {
             parent,
             inner: PulsePayload::new(&parent.selector, color),
-            attempts: None,
+            max_duration: MAX_EFFECT_DURATION,
+            retries: Retries::default(),
         }
     }
     /// Sets the starting color.
@@ -286,6 +558,29 @@ impl<'a, T: Select> Pulse<'a, T> {
         self.inner.from = Some(color);
         self
     }
+    /// Starts the effect from the current color of the bulb(s), clearing any starting color set
+    /// with [`from`](#method.from).
+    ///
+    /// This is the default behavior (the API uses the current color when no starting color is
+    /// sent); this method just makes that intent explicit.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() {
+    /// let secret = "foo";
+    /// let client = Client::new(secret);
+    /// let result = client
+    ///     .select(Selector::All)
+    ///     .pulse(Color::Green)
+    ///     .from_current()
+    ///     .send();
+    /// # }
+    /// ```
+    pub fn from_current(&mut self) -> &'_ mut Self {
+        self.inner.from = None;
+        self
+    }
     /// Sets the animation duration.
     ///
     /// ## Example
@@ -362,11 +657,43 @@ impl<'a, T: Select> Pulse<'a, T> {
         self.inner.power_on = Some(force);
         self
     }
+    /// Sets a limit on the total duration of the effect (period × cycles), checked locally.
+    ///
+    /// The API rejects overly long effects with a generic bad request error, but doesn't
+    /// document its limit, so a conservative one
+    /// ([`MAX_EFFECT_DURATION`](constant.MAX_EFFECT_DURATION.html)) is checked by default to fail
+    /// early with a descriptive error instead. This overrides that limit.
+    pub fn max_duration(&mut self, max: StdDuration) -> &'_ mut Self {
+        self.max_duration = max;
+        self
+    }
+    /// Checks the effect locally, ensuring that its total duration doesn't exceed the
+    /// [limit](#method.max_duration).
+    ///
+    /// This is also checked before sending, failing with
+    /// [`Error::InvalidEffect`](enum.Error.html#variant.InvalidEffect) without sending anything.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// use std::time::Duration;
+    /// let client = Client::new("foo");
+    /// let selected = client.select(Selector::All);
+    /// let mut pulse = selected.pulse(Color::Green);
+    /// assert!(pulse.cycles(10).validate().is_ok());
+    /// assert!(pulse.period(Duration::new(3600, 0)).validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<(), EffectValidationError> {
+        validate_duration(self.inner.period, self.inner.cycles, self.max_duration)
+    }
 }
 
 impl<'a, T: Select> Attempts for Pulse<'a, T> {
     fn set_attempts(&mut self, attempts: NonZeroU8) {
-        self.attempts = Some(attempts);
+        self.retries.attempts = Some(attempts);
+    }
+    fn set_backoff(&mut self, base: StdDuration) {
+        self.retries.backoff = Some(base);
     }
 }
 
@@ -384,6 +711,687 @@ impl<'a, T: Select> AsRequest<PulsePayload<'a, T>> for Pulse<'a, T> {
         &self.inner
     }
     fn attempts(&self) -> NonZeroU8 {
-        self.attempts.unwrap_or_else(unity)
+        self.retries.attempts()
+    }
+    fn retry_backoff(&self) -> Option<StdDuration> {
+        self.retries.backoff
+    }
+    fn guarded(&self) -> bool {
+        self.parent.guarded()
+    }
+    fn preflight(&self) -> Result<Option<String>, Error> {
+        self.validate().map_err(Error::InvalidEffect)?;
+        Ok(Some(self.path()))
+    }
+}
+
+#[derive(Clone, Default, Serialize)]
+#[doc(hidden)]
+/// The message constructed by the `Clean` request builder.
+pub struct CleanPayload {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration: Option<Duration>,
+}
+
+/// Starts (or stops) an HEV "clean" cycle on lights which support it.
+///
+/// Only HEV-capable products (see [`Product::hev`](../common/enum.Product.html#method.hev) and
+/// [`Light::supports_hev`](struct.Light.html#method.supports_hev)) accept the request.
+///
+/// ## Example
+/// ```
+/// use lifxi::http::prelude::*;
+/// # fn run() {
+/// let secret = "foo";
+/// let client = Client::new(secret);
+/// let result = client
+///     .select(Selector::Label("Bathroom".to_string()))
+///     .clean()
+///     .duration(::std::time::Duration::from_secs(2 * 60 * 60))
+///     .send();
+/// # }
+/// ```
+pub struct Clean<'a, T: Select> {
+    parent: &'a Selected<'a, T>,
+    inner: CleanPayload,
+    retries: Retries,
+}
+
+impl<'a, T: Select> Clean<'a, T> {
+    pub(crate) fn new(parent: &'a Selected<'a, T>) -> Self {
+        Self {
+            parent,
+            inner: CleanPayload::default(),
+            retries: Retries::default(),
+        }
+    }
+    /// Sets how long the cycle should run; if left unspecified (or zero), the device's default is
+    /// used.
+    pub fn duration<D: Into<Duration>>(&mut self, duration: D) -> &'_ mut Self {
+        self.inner.duration = Some(duration.into());
+        self
+    }
+    /// Sets whether to stop a running cycle instead of starting one.
+    pub fn stop(&mut self, stop: bool) -> &'_ mut Self {
+        self.inner.stop = Some(stop);
+        self
+    }
+}
+
+impl<'a, T: Select> Attempts for Clean<'a, T> {
+    fn set_attempts(&mut self, attempts: NonZeroU8) {
+        self.retries.attempts = Some(attempts);
+    }
+    fn set_backoff(&mut self, base: StdDuration) {
+        self.retries.backoff = Some(base);
+    }
+}
+
+impl<'a, T: Select> AsRequest<CleanPayload> for Clean<'a, T> {
+    fn method() -> reqwest::Method {
+        Method::POST
+    }
+    fn client(&self) -> &'_ Client {
+        self.parent.client
+    }
+    fn path(&self) -> String {
+        format!("/lights/{}/clean", self.parent.selector)
+    }
+    fn body(&self) -> &'_ CleanPayload {
+        &self.inner
+    }
+    fn attempts(&self) -> NonZeroU8 {
+        self.retries.attempts()
+    }
+    fn retry_backoff(&self) -> Option<StdDuration> {
+        self.retries.backoff
+    }
+    fn guarded(&self) -> bool {
+        self.parent.guarded()
+    }
+}
+
+/// The palette used by [`Morph`](struct.Morph.html) unless another one is given.
+fn rainbow() -> Vec<Color> {
+    vec![
+        Color::Red,
+        Color::Orange,
+        Color::Yellow,
+        Color::Green,
+        Color::Blue,
+        Color::Purple,
+        Color::Pink,
+    ]
+}
+
+#[derive(Clone, Serialize)]
+#[doc(hidden)]
+/// The message constructed by the `Morph` request builder.
+pub struct MorphPayload<'a, T: Select> {
+    selector: &'a T,
+    palette: Vec<Color>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    period: Option<Duration>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration: Option<Duration>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    power_on: Option<bool>,
+}
+
+impl<'a, T: Select> MorphPayload<'a, T> {
+    fn new(selector: &'a T) -> Self {
+        Self {
+            selector,
+            palette: rainbow(),
+            period: None,
+            duration: None,
+            power_on: None,
+        }
+    }
+}
+
+/// Specifies a "morph" effect (for tile devices), wherein the colors of a palette drift across
+/// the tiles.
+///
+/// Unless another palette is given, a rainbow (the named colors from red through pink) is used.
+///
+/// ## Example
+/// ```
+/// use lifxi::http::prelude::*;
+/// # fn run() {
+/// let secret = "foo";
+/// let client = Client::new(secret);
+/// let result = client
+///     .select(Selector::Label("Tiles".to_string()))
+///     .morph()
+///     .palette(vec![Color::Blue, Color::Purple, Color::Pink])
+///     .period(::std::time::Duration::new(10, 0))
+///     .send();
+/// # }
+/// ```
+pub struct Morph<'a, T: Select> {
+    parent: &'a Selected<'a, T>,
+    inner: MorphPayload<'a, T>,
+    retries: Retries,
+}
+
+impl<'a, T: Select> Morph<'a, T> {
+    pub(crate) fn new(parent: &'a Selected<'a, T>) -> Self {
+        Self {
+            parent,
+            inner: MorphPayload::new(&parent.selector),
+            retries: Retries::default(),
+        }
+    }
+    /// Sets the colors to morph between.
+    pub fn palette(&mut self, colors: Vec<Color>) -> &'_ mut Self {
+        self.inner.palette = colors;
+        self
+    }
+    /// Sets how long one cycle of the animation takes.
+    pub fn period<D: Into<Duration>>(&mut self, period: D) -> &'_ mut Self {
+        self.inner.period = Some(period.into());
+        self
+    }
+    /// Sets how long the effect should run; if left unspecified, it runs until stopped.
+    pub fn duration<D: Into<Duration>>(&mut self, duration: D) -> &'_ mut Self {
+        self.inner.duration = Some(duration.into());
+        self
+    }
+    /// Sets whether to power on the light if currently off.
+    pub fn power(&mut self, force: bool) -> &'_ mut Self {
+        self.inner.power_on = Some(force);
+        self
+    }
+    /// Checks the effect locally, ensuring that the palette has between one and
+    /// [`MAX_MORPH_PALETTE`](constant.MAX_MORPH_PALETTE.html) colors.
+    ///
+    /// This is also checked before sending, failing with
+    /// [`Error::InvalidEffect`](enum.Error.html#variant.InvalidEffect) without sending anything.
+    /// The API doesn't report how many tiles a device has, so the palette can't be checked
+    /// against that.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// let client = Client::new("foo");
+    /// let selected = client.select(Selector::All);
+    /// let mut morph = selected.morph();
+    /// assert!(morph.validate().is_ok());
+    /// assert!(morph.palette(vec![Color::Red; 17]).validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<(), EffectValidationError> {
+        match self.inner.palette.len() {
+            0 => Err(EffectValidationError::EmptyPalette),
+            n if n > MAX_MORPH_PALETTE => Err(EffectValidationError::PaletteTooLarge(n)),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<'a, T: Select> Attempts for Morph<'a, T> {
+    fn set_attempts(&mut self, attempts: NonZeroU8) {
+        self.retries.attempts = Some(attempts);
+    }
+    fn set_backoff(&mut self, base: StdDuration) {
+        self.retries.backoff = Some(base);
+    }
+}
+
+impl<'a, T: Select> AsRequest<MorphPayload<'a, T>> for Morph<'a, T> {
+    fn method() -> reqwest::Method {
+        Method::POST
+    }
+    fn client(&self) -> &'_ Client {
+        self.parent.client
+    }
+    fn path(&self) -> String {
+        format!("/lights/{}/effects/morph", self.parent.selector)
+    }
+    fn body(&self) -> &'_ MorphPayload<'a, T> {
+        &self.inner
+    }
+    fn attempts(&self) -> NonZeroU8 {
+        self.retries.attempts()
+    }
+    fn retry_backoff(&self) -> Option<StdDuration> {
+        self.retries.backoff
+    }
+    fn guarded(&self) -> bool {
+        self.parent.guarded()
+    }
+    fn preflight(&self) -> Result<Option<String>, Error> {
+        self.validate().map_err(Error::InvalidEffect)?;
+        Ok(Some(self.path()))
+    }
+}
+
+/// The direction in which a [`Move`](struct.Move.html) effect moves the zones of a strip, which
+/// shares the semantics of [`Direction`](enum.Direction.html): forward moves towards the end of
+/// the strip.
+pub type MoveDirection = Direction;
+
+#[derive(Clone, Serialize)]
+#[doc(hidden)]
+/// The message constructed by the `Move` request builder.
+pub struct MovePayload<'a, T: Select> {
+    selector: &'a T,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    direction: Option<Direction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    period: Option<Duration>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cycles: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    power_on: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fast: Option<bool>,
+}
+
+impl<'a, T: Select> MovePayload<'a, T> {
+    fn new(selector: &'a T) -> Self {
+        Self {
+            selector,
+            direction: None,
+            period: None,
+            cycles: None,
+            power_on: None,
+            fast: None,
+        }
+    }
+}
+
+/// Specifies a "move" effect, wherein the zones of a multizone device (e.g., a LIFX Z strip or
+/// Beam) scroll along it.
+///
+/// ## Example
+/// ```
+/// use lifxi::http::{prelude::*, Direction};
+/// # fn run() {
+/// let client = Client::new("foo");
+/// let result = client
+///     .select(Selector::Label("Strip".to_string()))
+///     .move_effect()
+///     .period(::std::time::Duration::new(2, 0))
+///     .direction(Direction::Forward)
+///     .send();
+/// # }
+/// ```
+pub struct Move<'a, T: Select> {
+    parent: &'a Selected<'a, T>,
+    inner: MovePayload<'a, T>,
+    retries: Retries,
+}
+
+impl<'a, T: Select> Move<'a, T> {
+    pub(crate) fn new(parent: &'a Selected<'a, T>) -> Self {
+        Self {
+            parent,
+            inner: MovePayload::new(&parent.selector),
+            retries: Retries::default(),
+        }
+    }
+    /// Sets the direction of movement; if left unspecified, the zones move forward (towards the
+    /// end of the strip).
+    pub fn direction(&mut self, direction: Direction) -> &'_ mut Self {
+        self.inner.direction = Some(direction);
+        self
+    }
+    /// Sets the time taken for one cycle of the effect.
+    pub fn period<D: Into<Duration>>(&mut self, period: D) -> &'_ mut Self {
+        self.inner.period = Some(period.into());
+        self
+    }
+    /// Sets the number of times to move the pattern along the strip; if left unspecified, the
+    /// effect continues until stopped (e.g., with
+    /// [`Selected::effects_off`](struct.Selected.html#method.effects_off)).
+    pub fn cycles(&mut self, count: f64) -> &'_ mut Self {
+        self.inner.cycles = Some(count);
+        self
+    }
+    /// Sets whether to power on the light if currently off.
+    pub fn power(&mut self, force: bool) -> &'_ mut Self {
+        self.inner.power_on = Some(force);
+        self
+    }
+    /// Sets whether to perform the action quickly (skipping checks and verification).
+    pub fn fast(&mut self, fast: bool) -> &'_ mut Self {
+        self.inner.fast = Some(fast);
+        self
+    }
+    /// Checks the effect locally, ensuring that the period (if specified) isn't zero.
+    ///
+    /// This is also checked before sending, failing with
+    /// [`Error::InvalidEffect`](enum.Error.html#variant.InvalidEffect) without sending anything.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// let client = Client::new("foo");
+    /// let selected = client.select(Selector::All);
+    /// let mut effect = selected.move_effect();
+    /// assert!(effect.period(::std::time::Duration::new(2, 0)).validate().is_ok());
+    /// assert!(effect.period(::std::time::Duration::new(0, 0)).validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<(), EffectValidationError> {
+        match self.inner.period {
+            Some(Duration(period)) if period == StdDuration::from_secs(0) => {
+                Err(EffectValidationError::ZeroPeriod)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<'a, T: Select> Attempts for Move<'a, T> {
+    fn set_attempts(&mut self, attempts: NonZeroU8) {
+        self.retries.attempts = Some(attempts);
+    }
+    fn set_backoff(&mut self, base: StdDuration) {
+        self.retries.backoff = Some(base);
+    }
+}
+
+impl<'a, T: Select> AsRequest<MovePayload<'a, T>> for Move<'a, T> {
+    fn method() -> reqwest::Method {
+        Method::POST
+    }
+    fn client(&self) -> &'_ Client {
+        self.parent.client
+    }
+    fn path(&self) -> String {
+        format!("/lights/{}/effects/move", self.parent.selector)
+    }
+    fn body(&self) -> &'_ MovePayload<'a, T> {
+        &self.inner
+    }
+    fn attempts(&self) -> NonZeroU8 {
+        self.retries.attempts()
+    }
+    fn retry_backoff(&self) -> Option<StdDuration> {
+        self.retries.backoff
+    }
+    fn guarded(&self) -> bool {
+        self.parent.guarded()
+    }
+    fn preflight(&self) -> Result<Option<String>, Error> {
+        self.validate().map_err(Error::InvalidEffect)?;
+        Ok(Some(self.path()))
+    }
+}
+
+#[derive(Clone, Default, Serialize)]
+#[doc(hidden)]
+/// The message constructed by the `EffectsOff` request builder.
+pub struct EffectsOffPayload {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    power_off: Option<bool>,
+}
+
+/// Stops any running effect (e.g., breathe, pulse, or morph).
+///
+/// ## Example
+/// ```
+/// use lifxi::http::prelude::*;
+/// # fn run() {
+/// let client = Client::new("foo");
+/// let all = client.select(Selector::All);
+/// let result = all.breathe(Color::Red).cycles(100).send();
+/// // Changed our mind.
+/// let result = all.effects_off().power_off(true).send();
+/// # }
+/// ```
+pub struct EffectsOff<'a, T: Select> {
+    parent: &'a Selected<'a, T>,
+    inner: EffectsOffPayload,
+    retries: Retries,
+}
+
+impl<'a, T: Select> EffectsOff<'a, T> {
+    pub(crate) fn new(parent: &'a Selected<'a, T>) -> Self {
+        Self {
+            parent,
+            inner: EffectsOffPayload::default(),
+            retries: Retries::default(),
+        }
+    }
+    /// Sets whether to also power the lights off once the effects are stopped.
+    pub fn power_off(&mut self, off: bool) -> &'_ mut Self {
+        self.inner.power_off = Some(off);
+        self
+    }
+}
+
+impl<'a, T: Select> Attempts for EffectsOff<'a, T> {
+    fn set_attempts(&mut self, attempts: NonZeroU8) {
+        self.retries.attempts = Some(attempts);
+    }
+    fn set_backoff(&mut self, base: StdDuration) {
+        self.retries.backoff = Some(base);
+    }
+}
+
+impl<'a, T: Select> AsRequest<EffectsOffPayload> for EffectsOff<'a, T> {
+    fn method() -> reqwest::Method {
+        Method::POST
+    }
+    fn client(&self) -> &'_ Client {
+        self.parent.client
+    }
+    fn path(&self) -> String {
+        format!("/lights/{}/effects/off", self.parent.selector)
+    }
+    fn body(&self) -> &'_ EffectsOffPayload {
+        &self.inner
+    }
+    fn attempts(&self) -> NonZeroU8 {
+        self.retries.attempts()
+    }
+    fn retry_backoff(&self) -> Option<StdDuration> {
+        self.retries.backoff
+    }
+    fn guarded(&self) -> bool {
+        self.parent.guarded()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::Selector;
+    use serde_json::json;
+    #[test]
+    fn breathe_payload() {
+        let client = Client::new("foo");
+        let selected = client.select(Selector::All);
+        let mut breathe = selected.breathe(Color::Orange);
+        let json = serde_json::to_value(&breathe.inner).expect("Failed to serialize breathe.");
+        assert_eq!(json, json!({ "color": "orange", "selector": "all" }));
+        breathe
+            .from(Color::Purple)
+            .power(true)
+            .cycles(3)
+            .period(StdDuration::from_millis(2500))
+            .peak(0.5)
+            .persist(true);
+        let json = serde_json::to_value(&breathe.inner).expect("Failed to serialize breathe.");
+        assert_eq!(
+            json,
+            json!({
+                "color": "orange",
+                "selector": "all",
+                "from_color": "purple",
+                "period": 2.5,
+                "cycles": 3,
+                "persist": true,
+                "power_on": true,
+                "peak": 0.5,
+            })
+        );
+        breathe.from_current();
+        let json = serde_json::to_value(&breathe.inner).expect("Failed to serialize breathe.");
+        assert!(json.get("from_color").is_none());
+        breathe.max_duration(StdDuration::from_secs(5));
+        match breathe.send() {
+            Err(Error::InvalidEffect(EffectValidationError::DurationTooLong(total, max))) => {
+                assert_eq!(total, StdDuration::from_millis(7500));
+                assert_eq!(max, StdDuration::from_secs(5));
+            }
+            other => panic!("Expected a validation error, got {:?}.", other),
+        }
+    }
+    #[test]
+    fn breathe_sequence() {
+        let client = Client::new("foo");
+        let selected = client.select(Selector::All);
+        let period = StdDuration::from_secs(2);
+        let colors = vec![Color::Red, Color::Green, Color::Blue];
+        let steps = selected.breathe_sequence(colors, period).steps();
+        let pairs = steps
+            .iter()
+            .map(|step| {
+                let json = serde_json::to_value(&step.inner).expect("Failed to serialize step.");
+                assert_eq!(json["cycles"], 1);
+                assert_eq!(json["period"], 2.0);
+                assert_eq!(json["persist"], true);
+                (json["from_color"].clone(), json["color"].clone())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pairs,
+            vec![
+                (json!("red"), json!("green")),
+                (json!("green"), json!("blue")),
+                (json!("blue"), json!("red")),
+            ]
+        );
+        let single = selected.breathe_sequence(vec![Color::Red], period);
+        assert!(single.steps().is_empty());
+    }
+    #[test]
+    fn morph_payload() {
+        let client = Client::new("foo");
+        let selected = client.select(Selector::All);
+        let mut morph = selected.morph();
+        let json = serde_json::to_value(&morph.inner).expect("Failed to serialize morph.");
+        assert_eq!(
+            json,
+            json!({
+                "selector": "all",
+                "palette": ["red", "orange", "yellow", "green", "blue", "purple", "pink"],
+            })
+        );
+        morph
+            .palette(vec![Color::Blue, Color::Hue(200)])
+            .period(StdDuration::from_secs(4))
+            .duration(StdDuration::from_secs(60))
+            .power(true);
+        let json = serde_json::to_value(&morph.inner).expect("Failed to serialize morph.");
+        assert_eq!(
+            json,
+            json!({
+                "selector": "all",
+                "palette": ["blue", "hue:200"],
+                "period": 4.0,
+                "duration": 60.0,
+                "power_on": true,
+            })
+        );
+        assert_eq!(
+            morph.palette(vec![Color::Red; 20]).validate(),
+            Err(EffectValidationError::PaletteTooLarge(20))
+        );
+        match morph.palette(vec![]).send() {
+            Err(Error::InvalidEffect(EffectValidationError::EmptyPalette)) => {}
+            other => panic!("Expected a validation error, got {:?}.", other),
+        }
+    }
+    #[test]
+    fn pulse_payload() {
+        let client = Client::new("foo");
+        let selected = client.select(Selector::All);
+        let mut pulse = selected.pulse(Color::Red);
+        let json = serde_json::to_value(&pulse.inner).expect("Failed to serialize pulse.");
+        assert_eq!(json, json!({ "color": "red", "selector": "all" }));
+        pulse
+            .from(Color::Blue)
+            .power(false)
+            .cycles(2)
+            .period(StdDuration::from_secs(1))
+            .persist(false);
+        let json = serde_json::to_value(&pulse.inner).expect("Failed to serialize pulse.");
+        assert_eq!(
+            json,
+            json!({
+                "color": "red",
+                "selector": "all",
+                "from_color": "blue",
+                "period": 1.0,
+                "cycles": 2,
+                "persist": false,
+                "power_on": false,
+            })
+        );
+        pulse.from_current();
+        let json = serde_json::to_value(&pulse.inner).expect("Failed to serialize pulse.");
+        assert!(json.get("from_color").is_none());
+        pulse.cycles(100).period(StdDuration::from_secs(60));
+        match pulse.validate() {
+            Err(EffectValidationError::DurationTooLong(total, max)) => {
+                assert_eq!(total, StdDuration::from_secs(6000));
+                assert_eq!(max, MAX_EFFECT_DURATION);
+            }
+            other => panic!("Expected a validation error, got {:?}.", other),
+        }
+        pulse.max_duration(StdDuration::from_secs(2 * 3600));
+        assert_eq!(pulse.validate(), Ok(()));
+    }
+    #[test]
+    fn effects_off_payload() {
+        let client = Client::new("foo");
+        let selected = client.select(Selector::Label("Desk".to_string()));
+        let mut off = selected.effects_off();
+        let json = serde_json::to_value(&off.inner).expect("Failed to serialize effects off.");
+        assert_eq!(json, json!({}));
+        assert_eq!(off.path(), "/lights/label:Desk/effects/off");
+        off.power_off(true);
+        let json = serde_json::to_value(&off.inner).expect("Failed to serialize effects off.");
+        assert_eq!(json, json!({ "power_off": true }));
+    }
+    #[test]
+    fn move_payload() {
+        let client = Client::new("foo");
+        let selected = client.select(Selector::All);
+        let mut effect = selected.move_effect();
+        let json = serde_json::to_value(&effect.inner).expect("Failed to serialize move.");
+        assert_eq!(json, json!({ "selector": "all" }));
+        effect
+            .direction(Direction::Backward)
+            .period(StdDuration::from_secs(2))
+            .cycles(1.5)
+            .power(true)
+            .fast(true);
+        let json = serde_json::to_value(&effect.inner).expect("Failed to serialize move.");
+        assert_eq!(
+            json,
+            json!({
+                "selector": "all",
+                "direction": "backward",
+                "period": 2.0,
+                "cycles": 1.5,
+                "power_on": true,
+                "fast": true,
+            })
+        );
+        effect.period(StdDuration::from_secs(0));
+        match effect.send() {
+            Err(Error::InvalidEffect(EffectValidationError::ZeroPeriod)) => {}
+            other => panic!("Expected a validation error, got {:?}.", other),
+        }
+        effect.direction(MoveDirection::Forward);
+        let json = serde_json::to_value(&effect.inner).expect("Failed to serialize move.");
+        assert_eq!(json["direction"], "forward");
     }
 }
@@ -1,22 +1,42 @@
+use std::collections::HashMap;
 use std::fmt;
 use std::num::NonZeroU8;
 use std::string::ToString;
+use std::sync::Mutex;
 use std::time::{Duration, Instant, SystemTime};
 
-use crate::http::{selector::Select, state::Color};
-use reqwest::{Client as ReqwestClient, Method};
-use serde::Serialize;
+use crate::http::{
+    light::{Light, LightStream, Lights},
+    reachability::{OperationResult, Reachability},
+    selector::{Select, Selector, Selectors},
+    state::Color,
+};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Client as ReqwestClient, Method,
+};
+use serde::{de::DeserializeOwned, Serialize};
 
 #[inline]
 pub(crate) fn unity() -> NonZeroU8 {
     NonZeroU8::new(1).expect("1 == 0")
 }
 
+#[cfg(feature = "async")]
+mod asynchronous;
 mod effects;
+mod rate_limit;
 mod scenes;
+mod sequence;
+mod spec;
 mod states;
+#[cfg(feature = "async")]
+pub use self::asynchronous::*;
 pub use self::effects::*;
+pub use self::rate_limit::*;
 pub use self::scenes::*;
+pub use self::sequence::*;
+pub use self::spec::*;
 pub use self::states::*;
 
 /// Contains useful utilities for working with the LIFX HTTP API.
@@ -28,16 +48,22 @@ pub use self::states::*;
 /// use lifxi::http::prelude::*;
 /// ```
 pub mod prelude {
+    pub use crate::common::Product;
     pub use crate::http::Client;
     pub use crate::http::Color;
     pub use crate::http::ColorParseError;
     pub use crate::http::ColorValidationError;
     pub use crate::http::Combine;
+    pub use crate::http::Light;
+    pub use crate::http::Lights;
     pub use crate::http::Randomize;
+    pub use crate::http::Reachability;
     pub use crate::http::Retry;
     pub use crate::http::Selector;
     pub use crate::http::SelectorParseError;
     pub use crate::http::Send;
+    #[cfg(feature = "async")]
+    pub use crate::http::SendAsync;
     pub use crate::http::State;
     pub use crate::http::StateChange;
 }
@@ -54,11 +80,106 @@ pub trait AsRequest<S: Serialize> {
     fn body(&self) -> &'_ S;
     /// The number of attempts to be made.
     fn attempts(&self) -> NonZeroU8;
+    /// The base delay for exponential backoff between retries, if any.
+    fn retry_backoff(&self) -> Option<Duration> {
+        None
+    }
+    /// Whether the request must be refused because it would affect the whole account without
+    /// confirmation (see [`ClientBuilder::guard_all`](struct.ClientBuilder.html#method.guard_all)).
+    fn guarded(&self) -> bool {
+        false
+    }
+    /// Performs any checks needed before sending, returning the path to which the request should
+    /// be sent, or `None` if there's nothing to send.
+    fn preflight(&self) -> Result<Option<String>, Error> {
+        Ok(Some(self.path()))
+    }
+}
+
+/// The initial wait before retrying a rate-limited request when the API gives no reset time.
+pub(crate) const RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(1);
+
+/// The longest to wait for a rate limit to be lifted before trying again, regardless of the reset
+/// time the API reports. Rate limits are per minute, so anything longer is a garbage header.
+pub(crate) const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// The retry settings of a configurable request.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Retries {
+    attempts: Option<NonZeroU8>,
+    backoff: Option<Duration>,
+}
+
+impl Retries {
+    /// The number of attempts to be made.
+    pub(crate) fn attempts(self) -> NonZeroU8 {
+        self.attempts.unwrap_or_else(unity)
+    }
 }
 
+/// The longest to wait between retries when backing off (see `Retry::backoff`).
+pub(crate) const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+
+/// The wait before the given (zero-based) retry when backing off exponentially from `base`.
+pub(crate) fn backoff_delay(base: Duration, retry: u32) -> Duration {
+    2u32.checked_pow(retry)
+        .and_then(|factor| base.checked_mul(factor))
+        .map_or(MAX_RETRY_BACKOFF, |delay| delay.min(MAX_RETRY_BACKOFF))
+}
+
+/// Percent-encodes a query string value, leaving only unreserved characters as they are.
+///
+/// Without this, a `#` (as in RGB strings) would start the URL fragment.
+fn encode_query_value(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                char::from(b).to_string()
+            }
+            b => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// The root of the API, to which request paths are appended.
+const DEFAULT_BASE_URL: &str = "https://api.lifx.com/v1";
+
 /// The result type for all requests made with the client.
 pub type ClientResult = Result<reqwest::Response, Error>;
 
+/// How often to poll while waiting for lights to become reachable.
+const REACHABILITY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How much of a non-JSON response body to include in an error.
+const SNIPPET_LENGTH: usize = 200;
+
+/// Ensures that the response claims to be JSON, returning a descriptive error otherwise.
+pub(crate) fn expect_json(mut response: reqwest::Response) -> ClientResult {
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("no content type")
+        .to_string();
+    if content_type.contains("json") {
+        Ok(response)
+    } else {
+        let body = response.text().unwrap_or_default();
+        let snippet = body.chars().take(SNIPPET_LENGTH).collect::<String>();
+        Err(Error::UnexpectedContentType(format!(
+            "{}: {}",
+            content_type, snippet
+        )))
+    }
+}
+
+/// Deserializes a JSON response body.
+pub(crate) fn parse_json<T: DeserializeOwned>(response: reqwest::Response) -> Result<T, Error> {
+    let response = expect_json(response)?;
+    serde_json::from_reader(response).map_err(Error::Deserialization)
+}
+
 /// The crux of the HTTP API. Start here.
 ///
 /// The client is the entry point for the web API interface. First construct a client, then use it
@@ -80,7 +201,45 @@ pub type ClientResult = Result<reqwest::Response, Error>;
 /// ```
 pub struct Client {
     client: ReqwestClient,
+    #[cfg(feature = "async")]
+    async_client: reqwest::r#async::Client,
     token: String,
+    base_url: String,
+    guard_all: bool,
+    cache: Option<LightCache>,
+    rate_limit: RateLimitTracker,
+}
+
+/// Recently listed lights, keyed by selector.
+struct LightCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Instant, Lights)>>,
+}
+
+impl LightCache {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+    fn get(&self, selector: &str) -> Option<Lights> {
+        let entries = self.entries.lock().ok()?;
+        entries
+            .get(selector)
+            .filter(|(fetched, _)| fetched.elapsed() < self.ttl)
+            .map(|(_, lights)| lights.clone())
+    }
+    fn insert(&self, selector: String, lights: &Lights) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(selector, (Instant::now(), lights.clone()));
+        }
+    }
+    fn clear(&mut self) {
+        if let Ok(entries) = self.entries.get_mut() {
+            entries.clear();
+        }
+    }
 }
 
 impl Client {
@@ -94,13 +253,121 @@ impl Client {
     /// let secret = "foo".to_string();
     /// let client = Client::new(secret);
     /// ```
-    #[allow(clippy::needless_pass_by_value)]
     pub fn new<S: ToString>(token: S) -> Self {
+        Self::with_client(token, ReqwestClient::new())
+    }
+    /// Constructs a new `Client` with the given access token, sending requests with the given
+    /// (preconfigured) HTTP client.
+    ///
+    /// Useful for configuring things this crate doesn't, like proxies or a user agent; see also
+    /// [`Client::builder`](#method.builder). Asynchronous requests still use a default client.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let http = reqwest::Client::builder()
+    ///     .timeout(::std::time::Duration::from_secs(10))
+    ///     .build()?;
+    /// let client = Client::with_client("foo", http);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn with_client<S: ToString>(token: S, client: ReqwestClient) -> Self {
         Self {
-            client: ReqwestClient::new(),
+            client,
+            #[cfg(feature = "async")]
+            async_client: reqwest::r#async::Client::new(),
             token: token.to_string(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            guard_all: false,
+            cache: None,
+            rate_limit: RateLimitTracker::new(RateLimitPolicy::default()),
         }
     }
+    /// Creates a builder for a `Client` with the given access token, for when the defaults used
+    /// by [`Client::new`](#method.new) aren't appropriate.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::builder("foo").guard_all(true).build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn builder<S: ToString>(token: S) -> ClientBuilder {
+        ClientBuilder::new(token.to_string())
+    }
+    /// Replaces the access token used by this client, keeping the underlying connection pool.
+    ///
+    /// Useful for applications whose (OAuth) tokens expire periodically. Any cached lights are
+    /// discarded.
+    ///
+    /// ## Examples
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// let mut client = Client::new("foo");
+    /// client.set_token("bar");
+    /// ```
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn set_token<S: ToString>(&mut self, token: S) {
+        self.token = token.to_string();
+        if let Some(cache) = self.cache.as_mut() {
+            cache.clear();
+        }
+        self.rate_limit.clear();
+    }
+    /// Replaces the root URL to which request paths are appended (by default,
+    /// `https://api.lifx.com/v1`), e.g. to point the client at a mock server.
+    ///
+    /// Any cached lights are discarded. See also
+    /// [`ClientBuilder::base_url`](struct.ClientBuilder.html#method.base_url).
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// let mut client = Client::new("foo");
+    /// client.set_base_url("http://localhost:8080/v1");
+    /// let summary = client.select(Selector::All).list().debug_summary();
+    /// assert!(summary.starts_with("GET http://localhost:8080/v1/lights/all"));
+    /// ```
+    pub fn set_base_url<S: Into<String>>(&mut self, url: S) {
+        self.base_url = url.into().trim_end_matches('/').to_string();
+        if let Some(cache) = self.cache.as_mut() {
+            cache.clear();
+        }
+    }
+    /// Returns this client with its access token replaced, keeping the underlying connection pool.
+    ///
+    /// ## Examples
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// let client = Client::new("foo").with_token("bar");
+    /// ```
+    pub fn with_token<S: ToString>(mut self, token: S) -> Self {
+        self.set_token(token);
+        self
+    }
+    /// The rate limit most recently reported by the API to this client, if any.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::new("foo");
+    /// client.select(Selector::All).list().send()?;
+    /// if let Some(limit) = client.rate_limit() {
+    ///     println!("{} requests left.", limit.remaining);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.rate_limit.last()
+    }
     /// Specifies the lights upon which to act.
     ///
     /// See [the documentation for `Selected<T>`](struct.Selected.html) to understand why this is
@@ -109,6 +376,7 @@ impl Client {
         Selected {
             client: self,
             selector,
+            confirmed: false,
         }
     }
     /// Creates a request to set multiple states (on multiple lights).
@@ -136,11 +404,141 @@ impl Client {
     pub fn validate(&self, color: &Color) -> Request<'_, ()> {
         Request {
             client: self,
-            path: format!("/color?string={}", color),
+            path: format!("/color?string={}", encode_query_value(&color.to_string())),
             body: (),
             method: Method::GET,
             attempts: unity(),
+            backoff: None,
+            headers: HeaderMap::new(),
+            guarded: false,
+        }
+    }
+    /// Fetches the lights matching each of several selectors in a single request.
+    ///
+    /// The selectors are combined into one `list` call, and the returned lights are then bucketed
+    /// back by (locally) matching each of them against each selector; a light may therefore
+    /// appear under several selectors.
+    ///
+    /// ## Notes
+    /// Labels, group names, and location names are matched exactly, and IDs are matched against
+    /// the light's serial number, group ID, or location ID as appropriate. `Selector::All` matches
+    /// every returned light. Scene membership isn't reported with lights, so `Selector::SceneId`
+    /// entries always map to an empty list.
+    ///
+    /// The API accepts at most 25 combined selectors; this is not enforced client-side.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::new("foo");
+    /// let lights = client.list_many(vec![
+    ///     Selector::Label("Desk".to_string()),
+    ///     Selector::Group("Kitchen".to_string()),
+    /// ])?;
+    /// let desk = &lights[&Selector::Label("Desk".to_string())];
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn list_many<I>(&self, selectors: I) -> Result<HashMap<Selector, Vec<Light>>, Error>
+    where
+        I: IntoIterator<Item = Selector>,
+    {
+        let selectors = selectors.into_iter().collect::<Vec<_>>();
+        if selectors.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let combined = selectors
+            .iter()
+            .cloned()
+            .fold(Selectors::default(), Selectors::combine);
+        let lights: Vec<Light> = parse_json(self.select(combined).list().send()?)?;
+        Ok(selectors
+            .into_iter()
+            .map(|selector| {
+                let matching = lights
+                    .iter()
+                    .filter(|light| light.matched_by(&selector))
+                    .cloned()
+                    .collect();
+                (selector, matching)
+            })
+            .collect())
+    }
+    /// Creates a handle which coalesces rapid state changes to the given lights, sending at most
+    /// about one request per window.
+    ///
+    /// See [`Debounced`](struct.Debounced.html) for the latency tradeoff.
+    pub fn debounced<T: Select>(&self, selector: T, window: Duration) -> Debounced<'_, T> {
+        Debounced::new(self.select(selector), window)
+    }
+    /// Resolves a selector to the (ID) selectors of the lights it currently matches.
+    ///
+    /// Labels and group or location names may change or become ambiguous; resolving them once
+    /// (e.g., at startup) pins subsequent requests to specific devices.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::new("foo");
+    /// let kitchen = client.resolve(Selector::Group("Kitchen".to_string()))?;
+    /// for id in kitchen {
+    ///     client.select(id).set_state().power(true).send()?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resolve<T: Select>(&self, selector: T) -> Result<Vec<Selector>, Error> {
+        Ok(self
+            .select(selector)
+            .lights()?
+            .iter()
+            .map(|light| Selector::Id(light.id.clone()))
+            .collect())
+    }
+    /// Starts an HEV "clean" cycle of the given duration on every light which supports it.
+    ///
+    /// All lights are listed first, and those which [support HEV](struct.Light.html#method.supports_hev)
+    /// are then cleaned (by ID, in a single request); other lights are skipped. Returns `Ok(None)`
+    /// (without sending anything further) if no light supports HEV.
+    ///
+    /// ## Notes
+    /// The API accepts at most 25 combined selectors; this is not enforced client-side.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::new("foo");
+    /// client.clean_all(::std::time::Duration::from_secs(2 * 60 * 60))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn clean_all(&self, duration: Duration) -> Result<Option<reqwest::Response>, Error> {
+        let ids = self
+            .select(Selector::All)
+            .list_fresh()?
+            .iter()
+            .filter(|light| light.supports_hev())
+            .map(|light| Selector::Id(light.id.clone()))
+            .collect::<Vec<_>>();
+        if ids.is_empty() {
+            return Ok(None);
         }
+        let selected = self.select(
+            ids.into_iter()
+                .fold(Selectors::default(), Selectors::combine),
+        );
+        let mut clean = selected.clean();
+        clean.duration(duration);
+        clean.send().map(Some)
+    }
+    /// Creates an (empty) sequence of requests to be sent in order.
+    ///
+    /// See [`Sequence`](struct.Sequence.html).
+    pub fn sequence(&self) -> Sequence<'_> {
+        Sequence::new(self)
     }
     /// Entry point for working with scenes.
     ///
@@ -150,6 +548,280 @@ impl Client {
     }
 }
 
+/// Configures and creates a [`Client`](struct.Client.html).
+///
+/// Created by [`Client::builder`](struct.Client.html#method.builder).
+pub struct ClientBuilder {
+    token: String,
+    guard_all: bool,
+    accept_language: Option<String>,
+    cache_ttl: Option<Duration>,
+    max_idle_per_host: Option<usize>,
+    rate_limit_policy: RateLimitPolicy,
+    base_url: Option<String>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+}
+
+impl ClientBuilder {
+    fn new(token: String) -> Self {
+        Self {
+            token,
+            guard_all: false,
+            accept_language: None,
+            cache_ttl: None,
+            max_idle_per_host: None,
+            rate_limit_policy: RateLimitPolicy::default(),
+            base_url: None,
+            timeout: None,
+            connect_timeout: None,
+        }
+    }
+    /// Sets the `Accept-Language` header sent with every request, so that (localized) error
+    /// messages come back in the given language(s).
+    ///
+    /// The value is passed through verbatim, so anything valid for the header (e.g., `"de"` or
+    /// `"fr-CH, fr;q=0.9"`) may be used.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::builder("foo").accept_language("de").build()?;
+    /// assert!(Client::builder("foo").accept_language("de\n").build().is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn accept_language(mut self, language: &str) -> Self {
+        self.accept_language = Some(language.to_string());
+        self
+    }
+    /// Sets whether requests affecting every light on the account must be explicitly confirmed.
+    ///
+    /// When enabled, state-changing requests whose selector matches all lights (e.g.,
+    /// `Selector::All`) fail with [`Error::UnconfirmedAll`](enum.Error.html#variant.UnconfirmedAll)
+    /// unless [`Selected::confirm_all`](struct.Selected.html#method.confirm_all) was called. The
+    /// same goes for [`set_states`](struct.Client.html#method.set_states) requests with any such
+    /// entry (see [`SetStates::confirm_all`](struct.SetStates.html#method.confirm_all)).
+    /// Listing lights is never guarded, and neither is activating a scene, which only affects the
+    /// lights saved in it. Disabled by default.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::builder("foo").guard_all(true).build()?;
+    /// // Fails without sending anything.
+    /// let result = client.select(Selector::All).toggle().send();
+    /// // Goes through.
+    /// let result = client.select(Selector::All).confirm_all().toggle().send();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn guard_all(mut self, guard: bool) -> Self {
+        self.guard_all = guard;
+        self
+    }
+    /// Caches the lights returned by [`Selected::lights`](struct.Selected.html#method.lights)
+    /// for the given duration, per selector, serving repeated calls without a network request.
+    ///
+    /// Useful when several parts of an application poll the same lights. Changes made in the
+    /// meantime (by this client or otherwise) aren't reflected until the cached entry expires; use
+    /// [`Selected::list_fresh`](struct.Selected.html#method.list_fresh) to bypass the cache.
+    /// Requests created by [`Selected::list`](struct.Selected.html#method.list) are never cached.
+    /// Disabled by default.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// use std::time::Duration;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::builder("foo")
+    ///     .cache_ttl(Duration::from_secs(5))
+    ///     .build()?;
+    /// let all = client.select(Selector::All);
+    /// let lights = all.lights()?;
+    /// // Served from the cache.
+    /// let lights = all.lights()?;
+    /// // Always hits the API.
+    /// let lights = all.list_fresh()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = Some(ttl);
+        self
+    }
+    /// Sets the maximum number of idle connections kept alive (per host) in the connection pool.
+    ///
+    /// Raising this can avoid connection churn when bursts of requests are sent concurrently
+    /// from several threads. Uses the HTTP client's default if unset.
+    ///
+    /// ## Notes
+    /// The underlying HTTP client doesn't support configuring how long idle connections are
+    /// kept, so no such option is offered.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::builder("foo").pool_max_idle_per_host(32).build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.max_idle_per_host = Some(max);
+        self
+    }
+    /// Sets what to do before sending a request once the API has reported that the rate limit
+    /// budget is used up; see [`RateLimitPolicy`](enum.RateLimitPolicy.html).
+    ///
+    /// The budget is tracked from the headers of every response to this client's (blocking)
+    /// requests, so long-running applications which poll lights can avoid being rejected at all.
+    /// Requests sent asynchronously aren't tracked.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::{prelude::*, RateLimitPolicy};
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::builder("foo")
+    ///     .rate_limit_policy(RateLimitPolicy::Wait)
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rate_limit_policy(mut self, policy: RateLimitPolicy) -> Self {
+        self.rate_limit_policy = policy;
+        self
+    }
+    /// Sets the root URL to which request paths are appended, in place of
+    /// `https://api.lifx.com/v1`.
+    ///
+    /// Useful for pointing the client at a mock server in tests, or at a proxy.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::builder("foo")
+    ///     .base_url("http://localhost:8080/v1")
+    ///     .build()?;
+    /// let summary = client.select(Selector::All).list().debug_summary();
+    /// assert!(summary.starts_with("GET http://localhost:8080/v1/lights/all"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn base_url(mut self, url: &str) -> Self {
+        self.base_url = Some(url.trim_end_matches('/').to_string());
+        self
+    }
+    /// Sets how long to wait for each request (from connecting until the response body is read)
+    /// before giving up with [`Error::Timeout`](enum.Error.html#variant.Timeout). Uses the HTTP
+    /// client's default if unset.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// use std::time::Duration;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::builder("foo")
+    ///     .timeout(Duration::from_secs(5))
+    ///     .connect_timeout(Duration::from_secs(2))
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+    /// Sets how long to wait for a connection to be established. Uses the HTTP client's default
+    /// if unset.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+    /// Creates the configured client.
+    ///
+    /// Fails if a configured header value is invalid, or if the underlying HTTP client can't be
+    /// initialized.
+    pub fn build(self) -> Result<Client, Error> {
+        let mut headers = HeaderMap::new();
+        if let Some(language) = self.accept_language {
+            let value =
+                HeaderValue::from_str(&language).map_err(|_| Error::InvalidHeader(language))?;
+            headers.insert(reqwest::header::ACCEPT_LANGUAGE, value);
+        }
+        #[cfg(feature = "async")]
+        let async_client = {
+            let mut client = reqwest::r#async::Client::builder().default_headers(headers.clone());
+            if let Some(max) = self.max_idle_per_host {
+                client = client.max_idle_per_host(max);
+            }
+            if let Some(timeout) = self.timeout {
+                client = client.timeout(timeout);
+            }
+            if let Some(timeout) = self.connect_timeout {
+                client = client.connect_timeout(timeout);
+            }
+            client.build()?
+        };
+        let mut client = ReqwestClient::builder().default_headers(headers);
+        if let Some(max) = self.max_idle_per_host {
+            client = client.max_idle_per_host(max);
+        }
+        if let Some(timeout) = self.timeout {
+            client = client.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            client = client.connect_timeout(timeout);
+        }
+        Ok(Client {
+            client: client.build()?,
+            #[cfg(feature = "async")]
+            async_client,
+            token: self.token,
+            base_url: self
+                .base_url
+                .unwrap_or_else(|| DEFAULT_BASE_URL.to_string()),
+            guard_all: self.guard_all,
+            cache: self.cache_ttl.map(LightCache::new),
+            rate_limit: RateLimitTracker::new(self.rate_limit_policy),
+        })
+    }
+}
+
+/// A problem with a specific field of a request, as reported by the API with a
+/// [`BadRequest`](enum.Error.html#variant.BadRequest) error.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct ApiErrorDetail {
+    /// The offending field of the request (e.g., `"color"`).
+    pub field: String,
+    /// What was wrong with it.
+    #[serde(default)]
+    pub message: Vec<String>,
+}
+
+/// The body of an error response.
+#[derive(Deserialize)]
+pub(crate) struct ApiErrorBody {
+    #[serde(default)]
+    pub(crate) errors: Vec<ApiErrorDetail>,
+}
+
+/// Whether the status indicates a malformed request, whose response describes what was wrong.
+pub(crate) fn is_bad_request(status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::BAD_REQUEST
+        || status == reqwest::StatusCode::UNPROCESSABLE_ENTITY
+}
+
+/// Reads the problems with specific fields from the body of an error response, if possible.
+pub(crate) fn error_details(body: &str) -> Vec<ApiErrorDetail> {
+    serde_json::from_str::<ApiErrorBody>(body)
+        .map(|body| body.errors)
+        .unwrap_or_default()
+}
+
 /// Represents an error encountered when sending a request.
 ///
 /// Errors may come from a variety of sources, but the ones handled most directly by this crate are
@@ -161,12 +833,13 @@ pub enum Error {
     /// The API is enforcing a rate limit. The associated value is the time at which the rate limit
     /// will be lifted, if it was specified.
     RateLimited(Option<Instant>),
-    /// The request was malformed and should not be reattempted (HTTP 400 or 422).
+    /// The request was malformed and should not be reattempted (HTTP 400 or 422). The problems
+    /// the API reported with specific fields are returned, if any.
     /// If this came from library methods, please
     /// [create an issue](https://github.com/Aehmlo/lifxi/issues/new). If you're using a custom
     /// color somewhere, please first [validate it](struct.Client.html#method.validate). Otherwise,
     /// check for empty strings.
-    BadRequest,
+    BadRequest(Vec<ApiErrorDetail>),
     /// The specified access token was invalid (HTTP 401).
     BadAccessToken,
     /// The requested OAuth scope was invalid (HTTP 403).
@@ -176,12 +849,33 @@ pub enum Error {
     NotFound(Option<String>),
     /// The API server encountered an error, but the request was (seemingly) valid (HTTP 5xx).
     Server(Option<reqwest::StatusCode>, reqwest::Error),
+    /// The request timed out. Unlike client errors, timeouts are retried.
+    Timeout(reqwest::Error),
     /// An HTTP stack error was encountered.
     Http(reqwest::Error),
     /// A serialization error was encountered.
     Serialization(reqwest::Error),
     /// A bad redirect was encountered.
     Redirect(reqwest::Error),
+    /// The response body could not be deserialized.
+    Deserialization(serde_json::Error),
+    /// The response wasn't JSON (e.g., an HTML error page served by a proxy during an outage).
+    /// The associated value is the reported content type followed by the start of the body.
+    UnexpectedContentType(String),
+    /// The request would affect every light on the account, but the client
+    /// [guards against that](struct.ClientBuilder.html#method.guard_all) and the request wasn't
+    /// [confirmed](struct.Selected.html#method.confirm_all). Nothing was sent.
+    UnconfirmedAll,
+    /// The selected lights didn't all become reachable before the deadline. The labels of the
+    /// lights which were still offline are returned.
+    Unreachable(Vec<String>),
+    /// A header value given to the [`ClientBuilder`](struct.ClientBuilder.html) was invalid. The
+    /// offending value is returned.
+    InvalidHeader(String),
+    /// An effect failed local validation, so nothing was sent.
+    InvalidEffect(EffectValidationError),
+    /// Zone colors failed local validation, so nothing was sent.
+    InvalidZones(ZoneValidationError),
     /// A miscellaneous client error occurred (HTTP 4xx).
     Client(Option<reqwest::StatusCode>, reqwest::Error),
     /// Some other error occured.
@@ -195,14 +889,52 @@ impl Error {
         use self::Error::*;
         match self {
             RateLimited(_)
-            | BadRequest
+            | BadRequest(_)
             | BadAccessToken
             | BadOAuthScope
             | NotFound(_)
+            | UnconfirmedAll
+            | InvalidHeader(_)
+            | InvalidEffect(_)
+            | InvalidZones(_)
+            | Unreachable(_)
             | Client(_, _) => true,
             _ => false,
         }
     }
+    /// The HTTP status code associated with the error, if any.
+    ///
+    /// Variants which don't carry a status code report the one they correspond to (e.g.,
+    /// `BadRequest` reports 400, though the API also uses 422 for malformed requests). Errors
+    /// which didn't come from a response (like timeouts or local validation) have no status.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::Error;
+    /// use reqwest::StatusCode;
+    /// assert_eq!(Error::BadAccessToken.status(), Some(StatusCode::UNAUTHORIZED));
+    /// assert_eq!(Error::UnconfirmedAll.status(), None);
+    /// ```
+    pub fn status(&self) -> Option<reqwest::StatusCode> {
+        use self::Error::*;
+        use reqwest::StatusCode;
+        match self {
+            RateLimited(_) => Some(StatusCode::TOO_MANY_REQUESTS),
+            BadRequest(_) => Some(StatusCode::BAD_REQUEST),
+            BadAccessToken => Some(StatusCode::UNAUTHORIZED),
+            BadOAuthScope => Some(StatusCode::FORBIDDEN),
+            NotFound(_) => Some(StatusCode::NOT_FOUND),
+            Server(status, e) | Client(status, e) => status.or_else(|| e.status()),
+            Timeout(e) | Http(e) | Serialization(e) | Redirect(e) | Other(e) => e.status(),
+            Deserialization(_)
+            | UnexpectedContentType(_)
+            | UnconfirmedAll
+            | Unreachable(_)
+            | InvalidHeader(_)
+            | InvalidEffect(_)
+            | InvalidZones(_) => None,
+        }
+    }
 }
 
 impl From<reqwest::Error> for Error {
@@ -212,13 +944,15 @@ impl From<reqwest::Error> for Error {
         if err.is_client_error() {
             match err.status() {
                 Some(StatusCode::BAD_REQUEST) | Some(StatusCode::UNPROCESSABLE_ENTITY) => {
-                    BadRequest
+                    BadRequest(Vec::new())
                 }
                 Some(StatusCode::UNAUTHORIZED) => BadAccessToken,
                 Some(StatusCode::FORBIDDEN) => BadOAuthScope,
                 Some(StatusCode::NOT_FOUND) => NotFound(err.url().map(|u| u.as_str().to_string())),
                 s => Client(s, err),
             }
+        } else if err.is_timeout() {
+            Timeout(err)
         } else if err.is_http() {
             Http(err)
         } else if err.is_serialization() {
@@ -239,12 +973,28 @@ impl fmt::Display for Error {
         match self {
             RateLimited(t) => {
                 if let Some(time) = t {
-                    write!(f, "Rate-limited until instant {:?}.", time)
+                    let wait = wait_until(*time);
+                    write!(
+                        f,
+                        "Rate-limited for another {}.{:03} s.",
+                        wait.as_secs(),
+                        wait.subsec_millis()
+                    )
                 } else {
                     write!(f, "Rate-limited.")
                 }
             }
-            BadRequest => write!(f, "Bad request."),
+            BadRequest(details) => {
+                if details.is_empty() {
+                    write!(f, "Bad request.")
+                } else {
+                    let details = details
+                        .iter()
+                        .map(|detail| format!("{}: {}", detail.field, detail.message.join("; ")))
+                        .collect::<Vec<_>>();
+                    write!(f, "Bad request: {}", details.join(", "))
+                }
+            }
             BadAccessToken => write!(f, "Bad access token."),
             BadOAuthScope => write!(f, "Bad OAuth scope."),
             NotFound(s) => {
@@ -254,27 +1004,97 @@ impl fmt::Display for Error {
                     write!(f, "Bad URL.")
                 }
             }
-            Server(_, e) => write!(f, "Server error: {}", e),
+            Server(Some(status), e) => write!(f, "Server error ({}): {}", status, e),
+            Server(None, e) => write!(f, "Server error: {}", e),
+            Timeout(e) => write!(f, "Request timed out: {}", e),
             Http(e) => write!(f, "HTTP error: {}", e),
             Serialization(e) => write!(f, "Serialization error: {}", e),
             Redirect(e) => write!(f, "Redirect error: {}", e),
-            Client(_, e) => write!(f, "Client error: {}", e),
+            Deserialization(e) => write!(f, "Deserialization error: {}", e),
+            UnexpectedContentType(s) => write!(f, "Expected a JSON response; got {}", s),
+            UnconfirmedAll => write!(f, "Unconfirmed request affecting all lights."),
+            InvalidHeader(s) => write!(f, "Invalid header value: {:?}", s),
+            InvalidEffect(e) => write!(f, "Invalid effect: {}", e),
+            InvalidZones(e) => write!(f, "Invalid zones: {}", e),
+            Unreachable(labels) => write!(f, "Lights still unreachable: {}", labels.join(", ")),
+            Client(Some(status), e) => write!(f, "Client error ({}): {}", status, e),
+            Client(None, e) => write!(f, "Client error: {}", e),
             Other(e) => write!(f, "{}", e),
         }
     }
 }
 
-impl ::std::error::Error for Error {}
+impl ::std::error::Error for Error {
+    /// Returns the wrapped error for variants stemming from the HTTP stack (or from
+    /// deserialization); errors synthesized by this crate have no source.
+    fn source(&self) -> Option<&(dyn ::std::error::Error + 'static)> {
+        use self::Error::*;
+        match self {
+            Server(_, e)
+            | Timeout(e)
+            | Http(e)
+            | Serialization(e)
+            | Redirect(e)
+            | Client(_, e)
+            | Other(e) => Some(e),
+            Deserialization(e) => Some(e),
+            InvalidEffect(e) => Some(e),
+            InvalidZones(e) => Some(e),
+            RateLimited(_)
+            | BadRequest(_)
+            | BadAccessToken
+            | BadOAuthScope
+            | NotFound(_)
+            | UnexpectedContentType(_)
+            | UnconfirmedAll
+            | Unreachable(_)
+            | InvalidHeader(_) => None,
+        }
+    }
+}
 
 /// Represents a terminal request.
 ///
-/// The only thing to be done with this request is [send it](#method.send).
+/// The only thing to be done with this request is [send it](#method.send) (optionally after
+/// [attaching headers](#method.header)).
 pub struct Request<'a, S> {
     client: &'a Client,
     path: String,
     body: S,
     method: Method,
     attempts: NonZeroU8,
+    backoff: Option<Duration>,
+    headers: HeaderMap,
+    guarded: bool,
+}
+
+impl<'a, S> Request<'a, S> {
+    /// Attaches an additional header to this request only.
+    ///
+    /// Custom headers are applied on top of the authorization and content type headers, so they
+    /// take precedence if they collide.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// use reqwest::header::{HeaderName, HeaderValue};
+    /// # fn run() {
+    /// let client = Client::new("foo");
+    /// let result = client
+    ///     .select(Selector::All)
+    ///     .toggle()
+    ///     .request()
+    ///     .header(
+    ///         HeaderName::from_static("x-request-id"),
+    ///         HeaderValue::from_static("1234"),
+    ///     )
+    ///     .send();
+    /// # }
+    /// ```
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
 }
 
 impl<'a, S> Request<'a, S>
@@ -284,58 +1104,237 @@ where
     /// Sends the request, returning the result.
     ///
     /// Requests are synchronous, so this method blocks.
+    ///
+    /// ## Retries
+    /// If more than one attempt was requested, server errors and timeouts are retried
+    /// immediately, or after an exponentially growing wait if
+    /// [backoff](trait.Retry.html#tymethod.backoff) was requested. When rate-limited, the next
+    /// attempt waits until the limit is lifted; if the API didn't say when that will be, the wait
+    /// starts at one second and doubles with each consecutive rate-limited attempt. Other client
+    /// errors are returned without retrying.
+    ///
+    /// Under [`RateLimitPolicy::Fail`](enum.RateLimitPolicy.html#variant.Fail), a request refused
+    /// because the rate limit budget is used up isn't retried.
     pub fn send(&self) -> ClientResult {
-        use reqwest::StatusCode;
-        let header = |name: &'static str| reqwest::header::HeaderName::from_static(name);
-        let token = self.client.token.as_str();
-        let client = &self.client.client;
-        let url = &format!("https://api.lifx.com/v1{}", self.path);
-        let method = self.method.clone();
-        let result = client
-            .request(method, url)
-            .bearer_auth(token)
-            .json(&self.body)
-            .send()?;
-        let headers = result.headers();
-        let reset = headers.get(&header("x-ratelimit-reset")).map(|s| {
-            if let Ok(val) = s.to_str() {
-                if let Ok(future) = val.parse::<u64>() {
-                    let now = (SystemTime::now(), Instant::now());
-                    if let Ok(timestamp) = now
-                        .0
-                        .duration_since(SystemTime::UNIX_EPOCH)
-                        .map(|t| t.as_secs())
-                    {
-                        return now.1 + Duration::from_secs(future - timestamp);
-                    }
-                }
-            }
-            Instant::now() + Duration::from_secs(60)
-        });
-        let mut result = result.error_for_status().map_err(|e| {
-            if e.status() == Some(StatusCode::TOO_MANY_REQUESTS) {
-                Error::RateLimited(reset)
-            } else {
-                e.into()
-            }
-        });
-        for _ in 1..self.attempts.get() {
+        if self.guarded {
+            return Err(Error::UnconfirmedAll);
+        }
+        let mut backoff = RATE_LIMIT_BACKOFF;
+        self.throttle()?;
+        let mut result = self.attempt();
+        for retry in 1..self.attempts.get() {
             match result {
                 Ok(r) => {
                     return Ok(r);
                 }
                 Err(e) => {
-                    if let Error::RateLimited(Some(t)) = e {
-                        // Wait until we're allowed to try again.
-                        ::std::thread::sleep(t - Instant::now());
-                    } else if e.is_client_error() {
-                        return Err(e);
+                    match e {
+                        Error::RateLimited(Some(t)) => {
+                            // Wait until we're allowed to try again.
+                            ::std::thread::sleep(wait_until(t));
+                        }
+                        Error::RateLimited(None) => {
+                            // We don't know how long to wait, so back off exponentially.
+                            ::std::thread::sleep(backoff);
+                            backoff *= 2;
+                        }
+                        _ if e.is_client_error() => return Err(e),
+                        _ => {
+                            if let Some(base) = self.backoff {
+                                ::std::thread::sleep(backoff_delay(base, u32::from(retry - 1)));
+                            }
+                        }
                     }
-                    result = self.send();
+                    self.throttle()?;
+                    result = self.attempt();
+                }
+            }
+        }
+        result
+    }
+    /// Produces a human-readable description of the request, suitable for pasting into a bug
+    /// report.
+    ///
+    /// The summary includes the method, URL, any custom headers, and the JSON body; the access
+    /// token is always redacted, as are the values of custom headers which may carry credentials
+    /// (e.g., `Authorization` or `Cookie`).
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// let client = Client::new("super secret");
+    /// let summary = client
+    ///     .select(Selector::All)
+    ///     .set_state()
+    ///     .power(true)
+    ///     .request()
+    ///     .debug_summary();
+    /// assert!(summary.starts_with("PUT https://api.lifx.com/v1/lights/all/state"));
+    /// assert!(summary.contains(r#""power": "on""#));
+    /// assert!(!summary.contains("super secret"));
+    /// ```
+    pub fn debug_summary(&self) -> String {
+        let mut summary = format!("{} {}\n", self.method, self.url());
+        summary.push_str("Authorization: Bearer <redacted>\n");
+        for (name, value) in &self.headers {
+            let value = if is_sensitive(name) {
+                "<redacted>"
+            } else {
+                value.to_str().unwrap_or("<non-ASCII value>")
+            };
+            summary.push_str(&format!("{}: {}\n", name, value));
+        }
+        match serde_json::to_value(&self.body) {
+            Ok(serde_json::Value::Null) => {}
+            Ok(body) => {
+                let body = serde_json::to_string_pretty(&body).unwrap_or_default();
+                summary.push_str(&format!("\n{}\n", body));
+            }
+            Err(e) => summary.push_str(&format!("\n<unserializable body: {}>\n", e)),
+        }
+        summary
+    }
+    /// Sends the request (as with [`send`](#method.send)), then deserializes the JSON response.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::{prelude::*, Light};
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::new("foo");
+    /// let lights: Vec<Light> = client.select(Selector::All).list().send_parsed()?;
+    /// for light in &lights {
+    ///     println!("{}: {:?}", light.label, light.reachability());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn send_parsed<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        parse_json(self.send()?)
+    }
+    /// Sends the request (as with [`send`](#method.send)), then reads which lights received it.
+    ///
+    /// See [`OperationResult::from_response`](struct.OperationResult.html#method.from_response).
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::new("foo");
+    /// let results = client
+    ///     .select(Selector::All)
+    ///     .set_state()
+    ///     .power(false)
+    ///     .request()
+    ///     .send_results()?;
+    /// for result in results.iter().filter(|r| r.status != Reachability::Ok) {
+    ///     println!("{} didn't turn off ({:?}).", result.label, result.status);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn send_results(&self) -> Result<Vec<OperationResult>, Error> {
+        OperationResult::from_response(self.send()?)
+    }
+    /// Sends the request (as with [`send`](#method.send)), also returning the rate limit reported
+    /// with the response, if any.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::new("foo");
+    /// let (response, limit) = client.select(Selector::All).list().send_with_meta()?;
+    /// if let Some(limit) = limit {
+    ///     println!("{} of {} requests left.", limit.remaining, limit.limit);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn send_with_meta(&self) -> Result<(reqwest::Response, Option<RateLimit>), Error> {
+        let response = self.send()?;
+        let limit = RateLimit::from_headers(response.headers());
+        Ok((response, limit))
+    }
+    fn url(&self) -> String {
+        format!("{}{}", self.client.base_url, self.path)
+    }
+    /// Applies the client's rate limit policy before an attempt, waiting or failing as needed.
+    fn throttle(&self) -> Result<(), Error> {
+        if let Some(wait) = self.client.rate_limit.check()? {
+            ::std::thread::sleep(wait);
+        }
+        Ok(())
+    }
+    /// Makes a single attempt at sending the request.
+    fn attempt(&self) -> ClientResult {
+        let token = self.client.token.as_str();
+        let client = &self.client.client;
+        let url = &self.url();
+        let method = self.method.clone();
+        let mut result = client
+            .request(method, url)
+            .bearer_auth(token)
+            .json(&self.body)
+            .headers(self.headers.clone())
+            .send()?;
+        self.client.rate_limit.record(result.headers());
+        if is_bad_request(result.status()) {
+            let body = result.text().unwrap_or_default();
+            return Err(Error::BadRequest(error_details(&body)));
+        }
+        let reset = rate_limit_reset(result.headers());
+        result
+            .error_for_status()
+            .map_err(|e| status_error(e, reset))
+    }
+}
+
+/// Whether a header's value may carry credentials, and so must be left out of debug summaries.
+fn is_sensitive(name: &HeaderName) -> bool {
+    use reqwest::header::{AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION, SET_COOKIE};
+    [AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION, SET_COOKIE].contains(name)
+        || ["token", "secret", "key"]
+            .iter()
+            .any(|word| name.as_str().contains(word))
+}
+
+/// Reads the time at which a rate limit will be lifted from the response headers, if present.
+pub(crate) fn rate_limit_reset(headers: &HeaderMap) -> Option<Instant> {
+    let header = reqwest::header::HeaderName::from_static("x-ratelimit-reset");
+    headers.get(&header).map(|s| {
+        if let Ok(val) = s.to_str() {
+            if let Ok(future) = val.parse::<u64>() {
+                let now = (SystemTime::now(), Instant::now());
+                if let Ok(timestamp) = now
+                    .0
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .map(|t| t.as_secs())
+                {
+                    let wait = Duration::from_secs(future.saturating_sub(timestamp));
+                    return now.1 + wait.min(MAX_RATE_LIMIT_WAIT);
                 }
             }
         }
-        result
+        Instant::now() + Duration::from_secs(60)
+    })
+}
+
+/// How long to wait for a rate limit to be lifted at the given time.
+///
+/// The time may already have passed (due to clock skew or slow handling, for instance), in which
+/// case there's no need to wait at all; the wait is also capped at `MAX_RATE_LIMIT_WAIT`.
+pub(crate) fn wait_until(reset: Instant) -> Duration {
+    reset
+        .saturating_duration_since(Instant::now())
+        .min(MAX_RATE_LIMIT_WAIT)
+}
+
+/// Converts an error status into an error, using the rate limit reset time if rate-limited.
+pub(crate) fn status_error(err: reqwest::Error, reset: Option<Instant>) -> Error {
+    if err.status() == Some(reqwest::StatusCode::TOO_MANY_REQUESTS) {
+        Error::RateLimited(reset)
+    } else {
+        err.into()
     }
 }
 
@@ -346,6 +1345,29 @@ pub trait Send<S> {
     /// This method delegates to `Request::send`, so take a look  at
     /// [that documentation](struct.Request.html#method.send) for more information.
     fn send(&self) -> ClientResult;
+    /// Sends the request, then reads which lights received it.
+    ///
+    /// See [`OperationResult::from_response`](struct.OperationResult.html#method.from_response).
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::new("foo");
+    /// let results = client.select(Selector::All).toggle().send_results()?;
+    /// for result in results.iter().filter(|r| r.status == Reachability::TimedOut) {
+    ///     println!("{} timed out.", result.label);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn send_results(&self) -> Result<Vec<OperationResult>, Error> {
+        OperationResult::from_response(self.send()?)
+    }
+    /// Converts the configured request into a terminal [`Request`](struct.Request.html), which
+    /// may be further customized (e.g., with [custom headers](struct.Request.html#method.header))
+    /// before being sent.
+    fn request(&self) -> Request<'_, &S>;
 }
 
 impl<'a, T, S> Send<S> for T
@@ -355,14 +1377,31 @@ where
 {
     /// Delegates to [`Request::send`](struct.Request.html#method.send).
     fn send(&self) -> ClientResult {
-        let request = Request {
+        match self.preflight()? {
+            Some(path) => {
+                let mut request = self.request();
+                request.path = path;
+                request.send()
+            }
+            None => {
+                // Nothing needed sending, so report an empty success.
+                let mut response = http::Response::new("");
+                *response.status_mut() = http::StatusCode::NO_CONTENT;
+                Ok(response.into())
+            }
+        }
+    }
+    fn request(&self) -> Request<'_, &S> {
+        Request {
             body: self.body(),
             client: self.client(),
             method: Self::method(),
             path: self.path(),
             attempts: self.attempts(),
-        };
-        request.send()
+            backoff: self.retry_backoff(),
+            headers: HeaderMap::new(),
+            guarded: self.guarded(),
+        }
     }
 }
 
@@ -371,12 +1410,17 @@ where
 pub trait Attempts {
     /// Updates the number of times to retry the request.
     fn set_attempts(&mut self, attempts: NonZeroU8);
+    /// Updates the base delay for backing off between retries.
+    fn set_backoff(&mut self, base: Duration);
 }
 
 impl<'a, S: Serialize> Attempts for Request<'a, S> {
     fn set_attempts(&mut self, attempts: NonZeroU8) {
         self.attempts = attempts;
     }
+    fn set_backoff(&mut self, base: Duration) {
+        self.backoff = Some(base);
+    }
 }
 
 /// Trait enabling retrying of failed requests.
@@ -385,6 +1429,28 @@ pub trait Retry {
     fn retry(&mut self) -> &'_ mut Self;
     /// Retries the corresponding request the given number of times.
     fn retries(&mut self, n: NonZeroU8) -> &'_ mut Self;
+    /// Waits between retries, starting at `base` and doubling with each retry (up to 30 seconds).
+    ///
+    /// By default, failed requests are retried immediately. This only affects server errors and
+    /// timeouts; rate-limited requests always wait for the limit to be lifted.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// use std::num::NonZeroU8;
+    /// use std::time::Duration;
+    /// # fn run() {
+    /// let client = Client::new("foo");
+    /// let result = client
+    ///     .select(Selector::All)
+    ///     .set_state()
+    ///     .power(true)
+    ///     .retries(NonZeroU8::new(3).unwrap())
+    ///     .backoff(Duration::from_millis(500))
+    ///     .send();
+    /// # }
+    /// ```
+    fn backoff(&mut self, base: Duration) -> &'_ mut Self;
 }
 
 impl<T> Retry for T
@@ -398,20 +1464,56 @@ where
         self.set_attempts(n);
         self
     }
+    fn backoff(&mut self, base: Duration) -> &'_ mut Self {
+        self.set_backoff(base);
+        self
+    }
 }
 
 /// A scoped request that can be used to get or set light states.
 ///
 /// Created by [`Client::select`](struct.Client.html#method.select).
+///
+/// ## Notes
+/// The HTTP API offers no way to rename (set the label of) a light; labels can only be changed
+/// from the LIFX app or over the LAN protocol (where they are limited to 32 bytes of UTF-8).
 pub struct Selected<'a, T: Select> {
     client: &'a Client,
     selector: T,
+    confirmed: bool,
 }
 
 impl<'a, T> Selected<'a, T>
 where
     T: Select,
 {
+    /// Confirms that requests on this selection may affect every light on the account.
+    ///
+    /// Only meaningful for clients built with
+    /// [`ClientBuilder::guard_all`](struct.ClientBuilder.html#method.guard_all); a no-op otherwise.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::builder("foo").guard_all(true).build()?;
+    /// let result = client
+    ///     .select(Selector::All)
+    ///     .confirm_all()
+    ///     .set_state()
+    ///     .power(false)
+    ///     .send();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn confirm_all(mut self) -> Self {
+        self.confirmed = true;
+        self
+    }
+    /// Whether state-changing requests on this selection must be refused.
+    pub(crate) fn guarded(&self) -> bool {
+        self.client.guard_all && !self.confirmed && self.selector.targets_all()
+    }
     /// Creates a request to get information about the selected lights (including their states).
     ///
     /// ## Example
@@ -432,6 +1534,137 @@ where
             body: (),
             method: Method::GET,
             attempts: unity(),
+            backoff: None,
+            headers: HeaderMap::new(),
+            guarded: false,
+        }
+    }
+    /// Lists the selected lights, deserializing the response.
+    ///
+    /// If the client was built with a
+    /// [cache TTL](struct.ClientBuilder.html#method.cache_ttl), a recent enough result for the
+    /// same selector is returned without contacting the API. Requests which list lights to decide
+    /// what to send (such as [`SetState::if_connected`](struct.SetState.html#method.if_connected)
+    /// and [`Client::clean_all`](struct.Client.html#method.clean_all)) always use
+    /// [`list_fresh`](#method.list_fresh) instead.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::new("foo");
+    /// let lights = client.select(Selector::All).lights()?;
+    /// for light in &lights {
+    ///     println!("{}: {}", light.label, light.brightness);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn lights(&'a self) -> Result<Lights, Error> {
+        let cached = self
+            .client
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.get(&self.selector.to_string()));
+        match cached {
+            Some(lights) => Ok(lights),
+            None => self.list_fresh(),
+        }
+    }
+    /// Lists the selected lights, deserializing the response, and always contacting the API.
+    ///
+    /// Like [`lights`](#method.lights), but ignores the client's cache (though the result still
+    /// replaces any cached one).
+    pub fn list_fresh(&'a self) -> Result<Lights, Error> {
+        let lights: Lights = parse_json(self.list().send()?)?;
+        if let Some(cache) = self.client.cache.as_ref() {
+            cache.insert(self.selector.to_string(), &lights);
+        }
+        Ok(lights)
+    }
+    /// Lists the selected lights, returning the response as untyped JSON.
+    ///
+    /// This is an escape hatch for reading fields which [`Light`](struct.Light.html) doesn't
+    /// (yet) expose. Like [`list`](#method.list), this is never served from the client's cache.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::new("foo");
+    /// let lights = client.select(Selector::All).list_value()?;
+    /// for light in lights.as_array().into_iter().flatten() {
+    ///     println!("{}", light["product"]["name"]);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn list_value(&'a self) -> Result<serde_json::Value, Error> {
+        parse_json(self.list().send()?)
+    }
+    /// Waits until all of the selected lights are reachable, polling (listing) them about once a
+    /// second, and returns them once they are.
+    ///
+    /// Useful for lights on a switched circuit which was just energized. If the lights still
+    /// aren't all reachable once the timeout has elapsed, an
+    /// [`Error::Unreachable`](enum.Error.html#variant.Unreachable) is returned; errors from
+    /// listing are returned immediately.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::new("foo");
+    /// let porch = client.select(Selector::Label("Porch".to_string()));
+    /// porch.wait_until_reachable(::std::time::Duration::from_secs(30))?;
+    /// porch.set_state().power(true).send()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn wait_until_reachable(&'a self, timeout: Duration) -> Result<Lights, Error> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let lights = self.list_fresh()?;
+            let offline = lights
+                .iter()
+                .filter(|light| light.reachability() != Reachability::Ok)
+                .map(|light| light.label.clone())
+                .collect::<Vec<_>>();
+            if offline.is_empty() {
+                return Ok(lights);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::Unreachable(offline));
+            }
+            ::std::thread::sleep(REACHABILITY_POLL_INTERVAL.min(deadline - now));
+        }
+    }
+    /// Lists the selected lights, deserializing them one at a time as the response is read.
+    ///
+    /// Unlike collecting the whole response at once, this only holds a single light in memory at
+    /// a time, which keeps peak memory usage low for very large accounts.
+    ///
+    /// ## Notes
+    /// The request is sent immediately, but the connection is held open for as long as the
+    /// iterator is alive. If the request itself fails, the iterator yields that error and ends.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() {
+    /// let client = Client::new("foo");
+    /// for light in client.select(Selector::All).lights_iter() {
+    ///     if let Ok(light) = light {
+    ///         println!("{}", light.label);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn lights_iter(&'a self) -> impl Iterator<Item = Result<Light, Error>> {
+        match self.list().send().and_then(expect_json) {
+            Ok(response) => LightStream::new(response),
+            Err(e) => LightStream::failed(e),
         }
     }
     /// Creates a request to set a uniform state on one or more lights.
@@ -455,6 +1688,53 @@ where
     pub fn set_state(&'a self) -> SetState<'a, T> {
         SetState::new(self)
     }
+    /// Creates a request to turn the selected lights on or off, which may be configured with a
+    /// transition time or fast mode.
+    ///
+    /// Unlike [`toggle`](#method.toggle), the resulting power state is explicit. Only the power
+    /// state (and any transition time) is sent, so the color and brightness of the lights are left
+    /// untouched. See [`SetPower`](struct.SetPower.html).
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() {
+    /// let client = Client::new("foo");
+    /// let result = client
+    ///     .select(Selector::All)
+    ///     .power(false)
+    ///     .over(::std::time::Duration::from_secs(10))
+    ///     .send();
+    /// # }
+    /// ```
+    pub fn power(&'a self, on: bool) -> SetPower<'a, T> {
+        SetPower::new(self, on)
+    }
+    /// Creates a request to turn the selected lights on or off, optionally over the given
+    /// transition time.
+    ///
+    /// This is shorthand for [`power`](#method.power) with the transition time preset; the
+    /// returned [`SetPower`](struct.SetPower.html) may be configured further.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() {
+    /// let client = Client::new("foo");
+    /// let office = client.select(Selector::Group("Office".to_string()));
+    /// let result = office.set_power(true, None).send();
+    /// let result = office
+    ///     .set_power(false, Some(::std::time::Duration::from_secs(3)))
+    ///     .send();
+    /// # }
+    /// ```
+    pub fn set_power(&'a self, on: bool, transition: Option<Duration>) -> SetPower<'a, T> {
+        let mut request = SetPower::new(self, on);
+        if let Some(transition) = transition {
+            request.transition(transition);
+        }
+        request
+    }
     /// Creates a request to incrementally change state on one or more lights.
     ///
     /// ## Example
@@ -478,6 +1758,61 @@ where
     pub fn change_state(&'a self) -> ChangeState<'a, T> {
         ChangeState::new(self)
     }
+    /// Creates a request to change the brightness of the selected lights by the given step.
+    ///
+    /// Positive steps brighten and negative steps darken. The change uses a short transition
+    /// ([`DIM_TRANSITION`](constant.DIM_TRANSITION.html)) so that many rapid calls don't pile up
+    /// long transitions; override it with
+    /// [`ChangeState::transition`](struct.ChangeState.html#method.transition) if desired.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() {
+    /// let client = Client::new("foo");
+    /// let office = client.select(Selector::Group("Office".to_string()));
+    /// let result = office.dim(-0.05).send();
+    /// let result = office
+    ///     .dim(0.05)
+    ///     .transition(::std::time::Duration::from_millis(50))
+    ///     .send();
+    /// # }
+    /// ```
+    pub fn dim(&'a self, step: f32) -> ChangeState<'a, T> {
+        let mut change = self.change_state();
+        change.brightness(step).transition(DIM_TRANSITION);
+        change
+    }
+    /// Creates a request to increase the brightness of the selected lights by the given step.
+    ///
+    /// Equivalent to [`dim`](#method.dim) with a positive step.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() {
+    /// let client = Client::new("foo");
+    /// let result = client.select(Selector::All).brighten(0.1).send();
+    /// # }
+    /// ```
+    pub fn brighten(&'a self, step: f32) -> ChangeState<'a, T> {
+        self.dim(step.abs())
+    }
+    /// Creates a request to decrease the brightness of the selected lights by the given step.
+    ///
+    /// Equivalent to [`dim`](#method.dim) with a negative step.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() {
+    /// let client = Client::new("foo");
+    /// let result = client.select(Selector::All).darken(0.1).send();
+    /// # }
+    /// ```
+    pub fn darken(&'a self, step: f32) -> ChangeState<'a, T> {
+        self.dim(-step.abs())
+    }
     /// Creates a request to begin a "breathe" effect.
     ///
     /// ## Example
@@ -500,6 +1835,32 @@ where
     pub fn breathe(&'a self, color: Color) -> Breathe<'a, T> {
         Breathe::new(self, color)
     }
+    /// Breathes through the given colors in turn, spending one period on each transition.
+    ///
+    /// This is a client-side composition of several breathe requests; see
+    /// [`BreatheSequence`](struct.BreatheSequence.html) for the timing details.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() {
+    /// let client = Client::new("foo");
+    /// let result = client
+    ///     .select(Selector::All)
+    ///     .breathe_sequence(
+    ///         vec![Color::Orange, Color::Purple],
+    ///         ::std::time::Duration::new(10, 0),
+    ///     )
+    ///     .send();
+    /// # }
+    /// ```
+    pub fn breathe_sequence(
+        &'a self,
+        colors: Vec<Color>,
+        period: Duration,
+    ) -> BreatheSequence<'a, T> {
+        BreatheSequence::new(self, colors, period)
+    }
     /// Creates a request to begin a "pulse" effect.
     ///
     /// ## Example
@@ -521,6 +1882,63 @@ where
     pub fn pulse(&'a self, color: Color) -> Pulse<'a, T> {
         Pulse::new(self, color)
     }
+    /// Creates a request to start an HEV "clean" cycle.
+    ///
+    /// See [`Clean`](struct.Clean.html); to clean every capable light, see
+    /// [`Client::clean_all`](struct.Client.html#method.clean_all).
+    pub fn clean(&'a self) -> Clean<'a, T> {
+        Clean::new(self)
+    }
+    /// Creates a request to begin a "morph" effect (on tile devices).
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() {
+    /// let client = Client::new("foo");
+    /// let result = client
+    ///     .select(Selector::Label("Tiles".to_string()))
+    ///     .morph()
+    ///     .send();
+    /// # }
+    /// ```
+    pub fn morph(&'a self) -> Morph<'a, T> {
+        Morph::new(self)
+    }
+    /// Creates a request to begin a "move" effect (on multizone devices).
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::{prelude::*, Direction};
+    /// # fn run() {
+    /// let client = Client::new("foo");
+    /// let result = client
+    ///     .select(Selector::Label("Strip".to_string()))
+    ///     .move_effect()
+    ///     .direction(Direction::Backward)
+    ///     .cycles(3.0)
+    ///     .send();
+    /// # }
+    /// ```
+    pub fn move_effect(&'a self) -> Move<'a, T> {
+        Move::new(self)
+    }
+    /// Creates a request to stop any running effects.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() {
+    /// let client = Client::new("foo");
+    /// let result = client
+    ///     .select(Selector::All)
+    ///     .effects_off()
+    ///     .send();
+    /// # }
+    /// ```
+    pub fn effects_off(&'a self) -> EffectsOff<'a, T> {
+        EffectsOff::new(self)
+    }
     /// Begins the process of specifying a cycle.
     ///
     /// Cycles provide a convenient method of moving through a set of changes without client-side
@@ -577,3 +1995,253 @@ where
         Toggle::new(self)
     }
 }
+
+impl<'a> Selected<'a, Selector> {
+    /// Creates a request to set the color of each zone of the selected multizone light(s).
+    ///
+    /// The first color is applied to zone 0, the second to zone 1, and so on (up to zone 255);
+    /// giving more colors is an error. Zones sharing a color are batched together, so most
+    /// gradients are applied in a single request (see [`SetZones`](struct.SetZones.html)).
+    ///
+    /// ## Notes
+    /// Zones only exist on multizone products (see
+    /// [`Product::multizone`](../common/enum.Product.html#method.multizone)); if the selected
+    /// lights are cached and any of them is known not to be one, nothing is sent. The HTTP API
+    /// applies zone changes immediately; the staged "apply" modes of the LAN protocol are not
+    /// exposed.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() {
+    /// let client = Client::new("foo");
+    /// let result = client
+    ///     .select(Selector::Label("Strip".to_string()))
+    ///     .set_zones(vec![Color::Red, Color::Orange, Color::Yellow])
+    ///     .send();
+    /// # }
+    /// ```
+    pub fn set_zones(&'a self, colors: Vec<Color>) -> SetZones<'a> {
+        SetZones::new(self, colors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn past_rate_limit_reset() {
+        let past = Instant::now()
+            .checked_sub(Duration::from_secs(5))
+            .unwrap_or_else(Instant::now);
+        assert_eq!(wait_until(past), Duration::from_secs(0));
+        assert_eq!(
+            Error::RateLimited(Some(past)).to_string(),
+            "Rate-limited for another 0.000 s."
+        );
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            HeaderName::from_static("x-ratelimit-reset"),
+            HeaderValue::from_static("1"),
+        );
+        let reset = rate_limit_reset(&headers).expect("Failed to read reset header.");
+        assert_eq!(wait_until(reset), Duration::from_secs(0));
+        assert_eq!(rate_limit_reset(&HeaderMap::new()), None);
+    }
+    #[test]
+    fn distant_rate_limit_reset() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            HeaderName::from_static("x-ratelimit-reset"),
+            HeaderValue::from_static("18446744073709551615"),
+        );
+        let reset = rate_limit_reset(&headers).expect("Failed to read reset header.");
+        let wait = wait_until(reset);
+        assert!(wait <= MAX_RATE_LIMIT_WAIT);
+        assert!(wait > MAX_RATE_LIMIT_WAIT - Duration::from_secs(5));
+    }
+    #[test]
+    fn bad_request_details() {
+        let body = r#"{
+            "error": "Validation error",
+            "errors": [
+                { "field": "color", "message": ["Unable to parse color: not_a_color"] },
+                { "field": "duration", "message": ["must be a number", "must be positive"] }
+            ],
+            "warnings": []
+        }"#;
+        let details = error_details(body);
+        assert_eq!(
+            details[0],
+            ApiErrorDetail {
+                field: "color".to_string(),
+                message: vec!["Unable to parse color: not_a_color".to_string()],
+            }
+        );
+        assert_eq!(details[1].message.len(), 2);
+        let err = Error::BadRequest(details);
+        assert_eq!(
+            err.to_string(),
+            "Bad request: color: Unable to parse color: not_a_color, \
+             duration: must be a number; must be positive"
+        );
+        assert!(error_details("<html>Oops</html>").is_empty());
+        assert!(error_details(r#"{"error": "Bad"}"#).is_empty());
+        assert_eq!(Error::BadRequest(Vec::new()).to_string(), "Bad request.");
+    }
+    #[test]
+    fn display() {
+        use std::error::Error as StdError;
+        let past = Instant::now()
+            .checked_sub(Duration::from_secs(5))
+            .unwrap_or_else(Instant::now);
+        let err = Error::RateLimited(Some(past));
+        assert_eq!(err.to_string(), "Rate-limited for another 0.000 s.");
+        assert!(err.source().is_none());
+        let url = "https://api.lifx.com/v1/lights/label:Nope".to_string();
+        let err = Error::NotFound(Some(url.clone()));
+        assert_eq!(err.to_string(), format!("Bad URL: {}", url));
+        let err: Box<dyn StdError> = Box::new(Error::UnconfirmedAll);
+        assert_eq!(err.to_string(), "Unconfirmed request affecting all lights.");
+    }
+    #[test]
+    fn debug_summary_redaction() {
+        let client = Client::new("super secret");
+        let selected = client.select(Selector::All);
+        let summary = selected
+            .toggle()
+            .request()
+            .header(
+                HeaderName::from_static("authorization"),
+                HeaderValue::from_static("Basic hunter2"),
+            )
+            .header(
+                HeaderName::from_static("cookie"),
+                HeaderValue::from_static("session=hunter2"),
+            )
+            .header(
+                HeaderName::from_static("x-api-key"),
+                HeaderValue::from_static("hunter2"),
+            )
+            .header(
+                HeaderName::from_static("x-request-id"),
+                HeaderValue::from_static("1234"),
+            )
+            .debug_summary();
+        assert!(!summary.contains("super secret"));
+        assert!(!summary.contains("hunter2"));
+        assert!(summary.contains("cookie: <redacted>"));
+        assert!(summary.contains("x-request-id: 1234"));
+    }
+    #[test]
+    fn validate_url() {
+        let client = Client::new("foo");
+        let color = Color::RgbStr("#ff0000".to_string());
+        assert_eq!(
+            client.validate(&color).url(),
+            "https://api.lifx.com/v1/color?string=%23ff0000"
+        );
+        let color = Color::Hsbk(Some(120), None, None, Some(3500));
+        assert_eq!(
+            client.validate(&color).url(),
+            "https://api.lifx.com/v1/color?string=hue%3A120%20kelvin%3A3500"
+        );
+    }
+    #[test]
+    fn timeout() {
+        use std::net::TcpListener;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        // A server which accepts connections but never responds.
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind listener.");
+        let addr = listener
+            .local_addr()
+            .expect("Failed to read listener address.");
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&accepted);
+        ::std::thread::spawn(move || {
+            let mut streams = Vec::new();
+            for stream in listener.incoming() {
+                counter.fetch_add(1, Ordering::SeqCst);
+                streams.push(stream);
+            }
+        });
+        let url = format!("http://{}", addr);
+        let err = reqwest::Client::builder()
+            .timeout(Duration::from_millis(100))
+            .build()
+            .expect("Failed to build HTTP client.")
+            .get(&url)
+            .send()
+            .err()
+            .expect("Request to a silent server succeeded.");
+        assert!(err.is_timeout());
+        let err = Error::from(err);
+        match &err {
+            Error::Timeout(_) => {}
+            other => panic!("Expected a timeout, got {:?}.", other),
+        }
+        assert!(!err.is_client_error());
+        // Unlike client errors, timeouts are retried.
+        let client = Client::builder("foo")
+            .base_url(&url)
+            .timeout(Duration::from_millis(100))
+            .build()
+            .expect("Failed to build client.");
+        let before = accepted.load(Ordering::SeqCst);
+        let selected = client.select(Selector::All);
+        let mut toggle = selected.toggle();
+        toggle.retries(NonZeroU8::new(2).expect("2 == 0"));
+        match toggle.send() {
+            Err(Error::Timeout(_)) => {}
+            other => panic!("Expected a timeout, got {:?}.", other),
+        }
+        assert_eq!(accepted.load(Ordering::SeqCst) - before, 2);
+    }
+    #[test]
+    fn rate_limit_fail_with_retries() {
+        let client = Client::builder("foo")
+            .rate_limit_policy(RateLimitPolicy::Fail)
+            .build()
+            .expect("Failed to build client.");
+        let reset = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|t| t.as_secs() + 30)
+            .unwrap_or_default();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-limit", HeaderValue::from_static("120"));
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("0"));
+        headers.insert(
+            "x-ratelimit-reset",
+            HeaderValue::from_str(&reset.to_string()).expect("Invalid header value."),
+        );
+        client.rate_limit.record(&headers);
+        let selected = client.select(Selector::Label("Desk".to_string()));
+        let mut toggle = selected.toggle();
+        toggle.retries(NonZeroU8::new(3).expect("3 == 0"));
+        let start = Instant::now();
+        match toggle.send() {
+            Err(Error::RateLimited(Some(_))) => {}
+            other => panic!("Expected a rate limit error, got {:?}.", other),
+        }
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+    #[test]
+    fn backoff() {
+        let base = Duration::from_millis(500);
+        assert_eq!(backoff_delay(base, 0), base);
+        assert_eq!(backoff_delay(base, 3), Duration::from_secs(4));
+        assert_eq!(backoff_delay(base, 10), MAX_RETRY_BACKOFF);
+        assert_eq!(backoff_delay(base, 40), MAX_RETRY_BACKOFF);
+        let client = Client::new("foo");
+        let selected = client.select(Selector::All);
+        let mut toggle = selected.toggle();
+        assert_eq!(Send::request(&toggle).backoff, None);
+        toggle.retry().backoff(base);
+        assert_eq!(Send::request(&toggle).backoff, Some(base));
+        assert_eq!(
+            toggle.transition(Duration::from_secs(1)).backoff,
+            Some(base)
+        );
+    }
+}
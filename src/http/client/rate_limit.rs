@@ -0,0 +1,185 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use reqwest::header::{HeaderMap, HeaderName};
+
+use crate::http::client::{rate_limit_reset, wait_until, Error};
+
+/// The state of the API's rate limit, as reported alongside a response.
+///
+/// Obtained with [`Request::send_with_meta`](struct.Request.html#method.send_with_meta).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RateLimit {
+    /// The number of requests allowed in each window.
+    pub limit: u32,
+    /// The number of requests left in the current window.
+    pub remaining: u32,
+    /// When the current window ends (and the budget is replenished), if reported.
+    pub reset: Option<Instant>,
+}
+
+/// Reads a numeric header.
+fn numeric_header(headers: &HeaderMap, name: &'static str) -> Option<u32> {
+    headers
+        .get(&HeaderName::from_static(name))
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+impl RateLimit {
+    /// Reads the rate limit from a response's headers.
+    ///
+    /// Returns `None` unless both the limit and the remaining budget are reported.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        Some(Self {
+            limit: numeric_header(headers, "x-ratelimit-limit")?,
+            remaining: numeric_header(headers, "x-ratelimit-remaining")?,
+            reset: rate_limit_reset(headers),
+        })
+    }
+    /// Whether the budget for the current window has been used up.
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining == 0
+    }
+}
+
+/// What a client does before sending a request once the API has reported that the rate limit
+/// budget is used up.
+///
+/// Set with [`ClientBuilder::rate_limit_policy`](struct.ClientBuilder.html#method.rate_limit_policy).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RateLimitPolicy {
+    /// Sends requests anyway, relying on [retries](trait.Retry.html) if they're rejected. This is
+    /// the default.
+    Ignore,
+    /// Waits (blocking the thread) for the current window to end before sending.
+    Wait,
+    /// Fails with [`Error::RateLimited`](enum.Error.html#variant.RateLimited) without sending
+    /// anything (and without retrying, even if [retries](trait.Retry.html) were requested).
+    Fail,
+}
+
+impl Default for RateLimitPolicy {
+    fn default() -> Self {
+        Self::Ignore
+    }
+}
+
+/// The rate limit most recently reported to a client, shared by all of its requests.
+pub(crate) struct RateLimitTracker {
+    policy: RateLimitPolicy,
+    last: Mutex<Option<RateLimit>>,
+}
+
+impl RateLimitTracker {
+    pub(crate) fn new(policy: RateLimitPolicy) -> Self {
+        Self {
+            policy,
+            last: Mutex::new(None),
+        }
+    }
+    /// The most recently reported rate limit, if any.
+    pub(crate) fn last(&self) -> Option<RateLimit> {
+        self.last.lock().ok().and_then(|last| *last)
+    }
+    /// Remembers the rate limit reported with a response, if any.
+    pub(crate) fn record(&self, headers: &HeaderMap) {
+        if let Some(limit) = RateLimit::from_headers(headers) {
+            if let Ok(mut last) = self.last.lock() {
+                *last = Some(limit);
+            }
+        }
+    }
+    /// Forgets the most recently reported rate limit (e.g., because the access token changed).
+    pub(crate) fn clear(&mut self) {
+        if let Ok(last) = self.last.get_mut() {
+            *last = None;
+        }
+    }
+    /// How long to wait before sending a request, according to the policy.
+    ///
+    /// Fails (under the `Fail` policy) if the budget is used up. If the end of the window wasn't
+    /// reported, there's no telling when to send, so requests go through.
+    pub(crate) fn check(&self) -> Result<Option<Duration>, Error> {
+        if self.policy == RateLimitPolicy::Ignore {
+            return Ok(None);
+        }
+        let reset = match self.last() {
+            Some(RateLimit {
+                remaining: 0,
+                reset: Some(reset),
+                ..
+            }) => reset,
+            _ => return Ok(None),
+        };
+        let wait = wait_until(reset);
+        if wait == Duration::from_secs(0) {
+            return Ok(None);
+        }
+        match self.policy {
+            RateLimitPolicy::Fail => Err(Error::RateLimited(Some(reset))),
+            _ => Ok(Some(wait)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    #[test]
+    fn from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-limit", HeaderValue::from_static("120"));
+        assert_eq!(RateLimit::from_headers(&headers), None);
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("0"));
+        let limit = RateLimit::from_headers(&headers).expect("Failed to read rate limit.");
+        assert_eq!(limit.limit, 120);
+        assert!(limit.is_exhausted());
+        assert_eq!(limit.reset, None);
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("119"));
+        headers.insert("x-ratelimit-reset", HeaderValue::from_static("1"));
+        let limit = RateLimit::from_headers(&headers).expect("Failed to read rate limit.");
+        assert!(!limit.is_exhausted());
+        assert!(limit.reset.is_some());
+    }
+    fn exhausted() -> HeaderMap {
+        let reset = ::std::time::SystemTime::now()
+            .duration_since(::std::time::UNIX_EPOCH)
+            .map(|t| t.as_secs() + 30)
+            .unwrap_or_default();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-limit", HeaderValue::from_static("120"));
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("0"));
+        headers.insert(
+            "x-ratelimit-reset",
+            HeaderValue::from_str(&reset.to_string()).expect("Invalid header value."),
+        );
+        headers
+    }
+    #[test]
+    fn policies() {
+        let headers = exhausted();
+        let tracker = RateLimitTracker::new(RateLimitPolicy::Ignore);
+        tracker.record(&headers);
+        assert!(tracker.last().map_or(false, |limit| limit.is_exhausted()));
+        assert!(tracker.check().expect("Ignored limit failed.").is_none());
+        let mut tracker = RateLimitTracker::new(RateLimitPolicy::Wait);
+        assert!(tracker.check().expect("Unknown limit failed.").is_none());
+        tracker.record(&headers);
+        let wait = tracker.check().expect("Waiting failed.");
+        assert!(wait.map_or(false, |wait| wait > Duration::from_secs(20)));
+        tracker.clear();
+        assert!(tracker.check().expect("Cleared limit failed.").is_none());
+        let tracker = RateLimitTracker::new(RateLimitPolicy::Fail);
+        tracker.record(&headers);
+        match tracker.check() {
+            Err(Error::RateLimited(Some(_))) => {}
+            other => panic!("Expected a rate limit error, got {:?}.", other),
+        }
+        let mut headers = headers;
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("1"));
+        tracker.record(&headers);
+        assert!(tracker.check().expect("Remaining budget failed.").is_none());
+    }
+}
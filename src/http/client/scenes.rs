@@ -1,9 +1,123 @@
 use crate::http::{
-    client::{unity, AsRequest, Attempts, Client, Request},
-    state::{Duration, State},
+    client::{
+        parse_json, state_matches, unity, AsRequest, Attempts, Client, Error, Request, Retries,
+        Send, SetStates,
+    },
+    selector::{Selector, SelectorParseError},
+    state::{Color, Duration, State},
 };
-use reqwest::Method;
+use reqwest::{header::HeaderMap, Method};
 use std::num::NonZeroU8;
+use std::time::{Duration as StdDuration, Instant};
+
+/// How often [`Activate::send_and_confirm`](struct.Activate.html#method.send_and_confirm) polls
+/// the lights in a scene.
+const CONFIRM_POLL_INTERVAL: StdDuration = StdDuration::from_secs(1);
+
+/// A scene associated with an account, as returned by
+/// [`Scenes::list_parsed`](struct.Scenes.html#method.list_parsed).
+///
+/// Scenes serialize to (and deserialize from) the same JSON shape the API uses, so they can be
+/// saved to disk and loaded back. More fields may be added as the API reports them, so scenes
+/// can't be constructed directly.
+///
+/// ## Example
+/// ```
+/// use lifxi::http::{prelude::*, Scene};
+/// # fn run() -> Result<(), lifxi::http::Error> {
+/// let client = Client::new("foo");
+/// let scenes: Vec<Scene> = client.scenes().list_parsed()?;
+/// for scene in &scenes {
+///     println!("{} ({} states)", scene.name, scene.states.len());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct Scene {
+    /// The unique ID of the scene.
+    pub uuid: String,
+    /// The human-readable name of the scene.
+    pub name: String,
+    /// The account which owns the scene, if reported.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account: Option<SceneAccount>,
+    /// The states which make up the scene.
+    pub states: Vec<SceneState>,
+    /// When the scene was created, as a Unix timestamp.
+    #[serde(default)]
+    pub created_at: u64,
+    /// When the scene was last modified, as a Unix timestamp.
+    #[serde(default)]
+    pub updated_at: u64,
+}
+
+/// The account which owns a [`Scene`](struct.Scene.html).
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SceneAccount {
+    /// The unique ID of the account.
+    pub uuid: String,
+}
+
+/// The state of some lights within a [`Scene`](struct.Scene.html).
+///
+/// Serializes to the same shape as an entry of a
+/// [`SetStates`](struct.SetStates.html) request.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SceneState {
+    /// The lights to which the state applies.
+    pub selector: Selector,
+    /// The state of those lights.
+    #[serde(flatten)]
+    pub state: State,
+}
+
+impl Scene {
+    /// Creates a request to (re-)apply the states of this scene directly.
+    ///
+    /// ## Notes
+    /// The HTTP API has no endpoint for creating scenes, so exported scenes can't be imported as
+    /// scenes (for instance, into another account); this is the closest substitute, applying the
+    /// saved states to the selected lights in a single batched request.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::{prelude::*, Scene};
+    /// # fn run(saved: &str) -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = Client::new("foo");
+    /// let scene: Scene = serde_json::from_str(saved)?;
+    /// let result = scene.restore(&client).send();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn restore<'a>(&self, client: &'a Client) -> SetStates<'a> {
+        let mut states = client.set_states();
+        states.add_all(
+            self.states
+                .iter()
+                .map(|entry| (entry.selector.clone(), entry.state.clone())),
+        );
+        states
+    }
+}
+
+/// Which lights reported a scene's states, as returned by
+/// [`Activate::send_and_confirm`](struct.Activate.html#method.send_and_confirm).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SceneConfirmation {
+    /// The IDs of the lights which reported their states from the scene.
+    pub confirmed: Vec<String>,
+    /// The IDs of the lights which still hadn't when the timeout elapsed.
+    pub unconfirmed: Vec<String>,
+}
+
+impl SceneConfirmation {
+    /// Whether every light in the scene reported its state.
+    pub fn is_complete(&self) -> bool {
+        self.unconfirmed.is_empty()
+    }
+}
 
 /// A waypoint in working with scenes.
 ///
@@ -34,8 +148,46 @@ impl<'a> Scenes<'a> {
             body: (),
             method: Method::GET,
             attempts: unity(),
+            backoff: None,
+            headers: HeaderMap::new(),
+            guarded: false,
         }
     }
+    /// Lists all scenes, deserializing the response.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::new("foo");
+    /// let scenes = client.scenes();
+    /// if let Some(scene) = scenes.list_parsed()?.iter().find(|scene| scene.name == "Chill") {
+    ///     let result = scenes.activate(&scene.uuid).send();
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn list_parsed(&'a self) -> Result<Vec<Scene>, Error> {
+        parse_json(self.list().send()?)
+    }
+    /// Lists all scenes for backup (an alias of [`list_parsed`](#method.list_parsed)).
+    ///
+    /// The scenes can be saved (e.g., as JSON) for backup; see
+    /// [`Scene::restore`](struct.Scene.html#method.restore) for how to apply them again.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = Client::new("foo");
+    /// let scenes = client.scenes().export()?;
+    /// let backup = serde_json::to_string(&scenes)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn export(&'a self) -> Result<Vec<Scene>, Error> {
+        self.list_parsed()
+    }
     /// Creates a configurable request for activating a specific scene.
     ///
     /// ## Example
@@ -57,6 +209,34 @@ impl<'a> Scenes<'a> {
     pub fn activate<S: ToString>(&'a self, uuid: S) -> Activate<'a> {
         Activate::new(self, uuid.to_string())
     }
+    /// Creates a configurable request for activating the scene identified by a
+    /// `Selector::SceneId`.
+    ///
+    /// Any other kind of selector doesn't identify a scene, and fails with
+    /// [`SelectorParseError::NotScene`](enum.SelectorParseError.html#variant.NotScene).
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), SelectorParseError> {
+    /// let client = Client::new("foo");
+    /// let scenes = client.scenes();
+    /// let result = scenes
+    ///     .activate_selector(Selector::SceneId("asdf".to_string()))?
+    ///     .send();
+    /// assert!(scenes.activate_selector(Selector::All).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn activate_selector(
+        &'a self,
+        selector: Selector,
+    ) -> Result<Activate<'a>, SelectorParseError> {
+        match selector {
+            Selector::SceneId(uuid) => Ok(self.activate(uuid)),
+            _ => Err(SelectorParseError::NotScene),
+        }
+    }
 }
 
 #[derive(Clone, Default, Serialize)]
@@ -94,7 +274,7 @@ pub struct Activate<'a> {
     parent: &'a Scenes<'a>,
     uuid: String,
     inner: ActivatePayload,
-    attempts: Option<NonZeroU8>,
+    retries: Retries,
 }
 
 impl<'a> Activate<'a> {
@@ -103,7 +283,7 @@ impl<'a> Activate<'a> {
             parent,
             uuid,
             inner: ActivatePayload::default(),
-            attempts: None,
+            retries: Retries::default(),
         }
     }
     /// Sets the transition time for the scene activation.
@@ -166,6 +346,12 @@ impl<'a> Activate<'a> {
     }
     /// Sets whether to perform the action quickly (skipping checks and verification).
     ///
+    /// ## Notes
+    /// This doesn't affect what is sent otherwise: a [transition](#method.transition) set
+    /// alongside it is still sent as the `duration`. Fast mode only makes the API skip its
+    /// initial state checks and return without waiting on the lights, and the API doesn't
+    /// document how it treats a transition in that case. If a fade matters, leave fast mode off.
+    ///
     /// ## Example
     /// ```
     /// use lifxi::http::prelude::*;
@@ -183,11 +369,120 @@ impl<'a> Activate<'a> {
         self.inner.fast = Some(fast);
         self
     }
+    /// The state lights should end up in after activation, given one from the scene.
+    ///
+    /// The overriding state (if any) takes priority, and ignored properties aren't expected.
+    fn expected(&self, state: &State) -> State {
+        let mut state = state.clone();
+        if let Some(overrides) = &self.inner.overrides {
+            if overrides.power.is_some() {
+                state.power = overrides.power;
+            }
+            if overrides.color.is_some() {
+                state.color = overrides.color.clone();
+            }
+            if overrides.brightness.is_some() {
+                state.brightness = overrides.brightness;
+                if let Some(Color::Hsbk(_, _, b, _)) = &mut state.color {
+                    *b = None;
+                }
+            }
+            if overrides.infrared.is_some() {
+                state.infrared = overrides.infrared;
+            }
+        }
+        for property in &self.inner.ignore {
+            match (property.as_str(), &mut state.color) {
+                ("power", _) => state.power = None,
+                ("infrared", _) => state.infrared = None,
+                ("color", _) => state.color = None,
+                ("brightness", Some(Color::Hsbk(_, _, b, _))) => {
+                    state.brightness = None;
+                    *b = None;
+                }
+                ("brightness", _) => state.brightness = None,
+                ("hue", Some(Color::Hsbk(h, _, _, _))) => *h = None,
+                ("saturation", Some(Color::Hsbk(_, s, _, _))) => *s = None,
+                ("kelvin", Some(Color::Hsbk(_, _, _, k))) => *k = None,
+                _ => {}
+            }
+        }
+        if let Some(Color::Hsbk(None, None, None, None)) = state.color {
+            state.color = None;
+        }
+        state
+    }
+    /// Activates the scene, then waits until its lights report the scene's states (within a
+    /// small tolerance), polling them about once a second.
+    ///
+    /// The scene is looked up first, so an unknown UUID fails with
+    /// [`Error::NotFound`](enum.Error.html#variant.NotFound) before anything is changed. Any
+    /// [overriding state](#method.overwrite) and [ignored properties](#method.ignore) are
+    /// accounted for. Once every light matches (or the timeout elapses), the lights are sorted
+    /// into those which confirmed and those which didn't; errors are returned immediately.
+    ///
+    /// ## Notes
+    /// Infrared levels aren't reported by the API, so they can't be confirmed. Lights set to
+    /// named, RGB, or custom colors never confirm, but scenes saved by the API come back with
+    /// HSBK colors.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::new("foo");
+    /// let scenes = client.scenes();
+    /// let confirmation = scenes
+    ///     .activate("asdf")
+    ///     .send_and_confirm(::std::time::Duration::from_secs(10))?;
+    /// if !confirmation.is_complete() {
+    ///     println!("Still waiting on {:?}.", confirmation.unconfirmed);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn send_and_confirm(&self, timeout: StdDuration) -> Result<SceneConfirmation, Error> {
+        let scene = self
+            .parent
+            .list_parsed()?
+            .into_iter()
+            .find(|scene| scene.uuid == self.uuid)
+            .ok_or(Error::NotFound(None))?;
+        let expected = scene
+            .states
+            .iter()
+            .map(|entry| (entry.selector.clone(), self.expected(&entry.state)))
+            .collect::<Vec<_>>();
+        self.send()?;
+        let client = self.parent.client;
+        let deadline = Instant::now() + timeout;
+        loop {
+            let mut confirmation = SceneConfirmation::default();
+            for (selector, state) in &expected {
+                let selected = client.select(selector.clone());
+                for light in selected.list_fresh()?.iter() {
+                    if state_matches(state, light) {
+                        confirmation.confirmed.push(light.id.clone());
+                    } else {
+                        confirmation.unconfirmed.push(light.id.clone());
+                    }
+                }
+            }
+            let now = Instant::now();
+            if confirmation.is_complete() || now >= deadline {
+                return Ok(confirmation);
+            }
+            ::std::thread::sleep(CONFIRM_POLL_INTERVAL.min(deadline - now));
+        }
+    }
 }
 
 impl<'a> Attempts for Activate<'a> {
     fn set_attempts(&mut self, attempts: NonZeroU8) {
-        self.attempts = Some(attempts);
+        self.retries.attempts = Some(attempts);
+    }
+    fn set_backoff(&mut self, base: StdDuration) {
+        self.retries.backoff = Some(base);
     }
 }
 
@@ -205,6 +500,105 @@ impl<'a> AsRequest<ActivatePayload> for Activate<'a> {
         &self.inner
     }
     fn attempts(&self) -> NonZeroU8 {
-        self.attempts.unwrap_or_else(unity)
+        self.retries.attempts()
+    }
+    fn retry_backoff(&self) -> Option<StdDuration> {
+        self.retries.backoff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{Color, Light, Power};
+    const SCENE: &str = r#"{
+        "uuid": "036dfaf0-6a8a-4a80-8749-8d3e09d35e2b",
+        "name": "Chill",
+        "account": { "uuid": "1a6ba4e7-53d8-468c-8e7e-3a1ee6fe3c3e" },
+        "states": [
+            {
+                "selector": "id:d3b2f2d97452",
+                "power": "on",
+                "brightness": 0.5,
+                "color": { "hue": 120.0, "saturation": 0.5, "kelvin": 3500 }
+            }
+        ],
+        "created_at": 1449105830,
+        "updated_at": 1449105830
+    }"#;
+    #[test]
+    fn round_trip() {
+        let scene: Scene = serde_json::from_str(SCENE).expect("Failed to parse scene.");
+        assert_eq!(scene.name, "Chill");
+        assert_eq!(
+            scene.account.as_ref().map(|account| account.uuid.as_str()),
+            Some("1a6ba4e7-53d8-468c-8e7e-3a1ee6fe3c3e")
+        );
+        let state = &scene.states[0];
+        assert_eq!(state.selector, Selector::Id("d3b2f2d97452".to_string()));
+        assert_eq!(state.state.power, Some(Power::from(true)));
+        assert_eq!(
+            state.state.color,
+            Some(Color::Hsbk(Some(120), Some(0.5), None, Some(3500)))
+        );
+        let json = serde_json::to_string(&scene).expect("Failed to serialize scene.");
+        let parsed: Scene = serde_json::from_str(&json).expect("Failed to reparse scene.");
+        assert_eq!(parsed.states[0].state.color, state.state.color);
+        assert_eq!(parsed.created_at, 1_449_105_830);
+        assert_eq!(parsed.account, scene.account);
+    }
+    #[test]
+    fn fast_with_transition() {
+        use std::time::Duration;
+        let client = Client::new("foo");
+        let scenes = client.scenes();
+        let mut activate = scenes.activate("asdf");
+        activate.transition(Duration::from_secs(2)).fast(true);
+        let json = serde_json::to_value(&activate.inner).expect("Failed to serialize payload.");
+        assert_eq!(json, serde_json::json!({"duration": 2.0, "fast": true}));
+        let mut activate = scenes.activate("asdf");
+        activate.transition(Duration::from_secs(2));
+        let json = serde_json::to_value(&activate.inner).expect("Failed to serialize payload.");
+        assert_eq!(json, serde_json::json!({"duration": 2.0}));
+    }
+    #[test]
+    fn confirmation() {
+        let light: Light = serde_json::from_str(
+            r#"{
+                "id": "d3b2f2d97452", "uuid": "", "label": "Desk", "connected": true,
+                "power": "on", "brightness": 0.3,
+                "color": { "hue": 120.0, "saturation": 0.5, "kelvin": 3500 },
+                "group": { "id": "g", "name": "Lounge" },
+                "location": { "id": "l", "name": "Home" }
+            }"#,
+        )
+        .expect("Failed to parse light.");
+        let client = Client::new("foo");
+        let scenes = client.scenes();
+        let scene: Scene = serde_json::from_str(SCENE).expect("Failed to parse scene.");
+        let state = &scene.states[0].state;
+        let mut activate = scenes.activate(&scene.uuid);
+        assert!(!state_matches(&activate.expected(state), &light));
+        activate.ignore("brightness");
+        assert!(state_matches(&activate.expected(state), &light));
+        let mut activate = scenes.activate(&scene.uuid);
+        activate.overwrite(State::builder().brightness(0.3));
+        let expected = activate.expected(state);
+        assert_eq!(expected.brightness, Some(0.3));
+        assert!(state_matches(&expected, &light));
+        activate.overwrite(State::builder().power(false));
+        assert!(!state_matches(&activate.expected(state), &light));
+    }
+    #[test]
+    fn restore() {
+        let client = Client::new("foo");
+        let scene: Scene = serde_json::from_str(SCENE).expect("Failed to parse scene.");
+        let states = scene.restore(&client);
+        let json = serde_json::to_value(states.body()).expect("Failed to serialize states.");
+        assert_eq!(json["states"][0]["selector"], "id:d3b2f2d97452");
+        assert_eq!(
+            json["states"][0]["color"],
+            "hue:120 saturation:0.5 kelvin:3500"
+        );
     }
 }
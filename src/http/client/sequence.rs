@@ -0,0 +1,163 @@
+use std::time::Instant;
+
+use crate::http::client::{Client, ClientResult, Error, Send, RATE_LIMIT_BACKOFF};
+
+/// A queued request, type-erased so that different builders can share a sequence.
+type Step<'a> = Box<dyn Fn() -> ClientResult + 'a>;
+
+/// Queues several requests (state changes, effects, toggles, ...) to be sent one after another.
+///
+/// Created by [`Client::sequence`](struct.Client.html#method.sequence), this allows reusable
+/// multi-step routines to be defined up front and then [run](#method.run) as a unit.
+///
+/// ## Notes
+/// Steps are sent strictly in the order in which they were added, each only after the previous
+/// one has completed. By default, a failing step doesn't stop the sequence: every step is
+/// attempted, and the result of each is reported in order (see
+/// [`stop_on_error`](#method.stop_on_error) to change this). If a step is rate-limited, it is
+/// retried (once) after waiting out the rate limit, so that later steps aren't sent into the same
+/// limit.
+///
+/// ## Example
+/// ```
+/// use lifxi::http::prelude::*;
+/// # fn run() {
+/// let client = Client::new("foo");
+/// let all = client.select(Selector::All);
+/// let accents = client.select(Selector::Group("Accents".to_string()));
+/// let mut warm = all.set_state();
+/// warm.color(Color::Kelvin(2700)).power(true);
+/// let mut pulse = accents.pulse(Color::Red);
+/// pulse.cycles(3);
+/// let results = client.sequence().then(warm).then(pulse).run();
+/// let failures = results.iter().filter(|result| result.is_err()).count();
+/// # }
+/// ```
+pub struct Sequence<'a> {
+    client: &'a Client,
+    steps: Vec<Step<'a>>,
+    stop_on_error: bool,
+}
+
+impl<'a> Sequence<'a> {
+    pub(crate) fn new(client: &'a Client) -> Self {
+        Self {
+            client,
+            steps: Vec::new(),
+            stop_on_error: false,
+        }
+    }
+    /// The client for which this sequence was created.
+    pub fn client(&self) -> &'a Client {
+        self.client
+    }
+    /// Queues a request to be sent after all previously-queued ones.
+    pub fn then<S, R>(&mut self, request: R) -> &'_ mut Self
+    where
+        R: Send<S> + 'a,
+    {
+        self.steps.push(Box::new(move || request.send()));
+        self
+    }
+    /// The number of queued steps.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+    /// Whether no steps have been queued.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+    /// Sets whether to stop at the first failing step, leaving later steps unsent.
+    ///
+    /// When stopping, the failing step's result is the last one returned.
+    pub fn stop_on_error(&mut self, stop: bool) -> &'_ mut Self {
+        self.stop_on_error = stop;
+        self
+    }
+    /// Sends each queued request in order, returning their results (in the same order).
+    pub fn run(&self) -> Vec<ClientResult> {
+        let mut results = Vec::with_capacity(self.steps.len());
+        for step in &self.steps {
+            let result = match step() {
+                Err(Error::RateLimited(reset)) => {
+                    let wait = reset.map_or(RATE_LIMIT_BACKOFF, |t| {
+                        t.saturating_duration_since(Instant::now())
+                    });
+                    ::std::thread::sleep(wait);
+                    step()
+                }
+                result => result,
+            };
+            let failed = result.is_err();
+            results.push(result);
+            if failed && self.stop_on_error {
+                break;
+            }
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    /// Queues a step which logs its index when sent, failing with a distinguishable error.
+    fn push<'a>(sequence: &mut Sequence<'a>, log: &'a RefCell<Vec<usize>>, i: usize) {
+        sequence.steps.push(Box::new(move || {
+            log.borrow_mut().push(i);
+            Err(Error::NotFound(Some(i.to_string())))
+        }));
+    }
+    /// The indices reported by the results of a sequence of logging steps.
+    fn indices(results: Vec<ClientResult>) -> Vec<String> {
+        results
+            .into_iter()
+            .map(|result| match result {
+                Err(Error::NotFound(Some(i))) => i,
+                other => panic!("Unexpected result: {:?}.", other),
+            })
+            .collect()
+    }
+    #[test]
+    fn order() {
+        let log = RefCell::new(Vec::new());
+        let client = Client::new("foo");
+        let mut sequence = client.sequence();
+        for i in 0..3 {
+            push(&mut sequence, &log, i);
+        }
+        assert_eq!(sequence.len(), 3);
+        // Failures don't stop the sequence by default.
+        assert_eq!(indices(sequence.run()), vec!["0", "1", "2"]);
+        assert_eq!(*log.borrow(), vec![0, 1, 2]);
+    }
+    #[test]
+    fn stop_on_error() {
+        let log = RefCell::new(Vec::new());
+        let client = Client::new("foo");
+        let mut sequence = client.sequence();
+        for i in 0..3 {
+            push(&mut sequence, &log, i);
+        }
+        sequence.stop_on_error(true);
+        assert_eq!(indices(sequence.run()), vec!["0"]);
+        assert_eq!(*log.borrow(), vec![0]);
+    }
+    #[test]
+    fn rate_limited() {
+        let log = RefCell::new(Vec::new());
+        let past = Instant::now();
+        let client = Client::new("foo");
+        let mut sequence = client.sequence();
+        sequence.steps.push(Box::new(|| {
+            log.borrow_mut().push(0);
+            Err(Error::RateLimited(Some(past)))
+        }));
+        push(&mut sequence, &log, 1);
+        let results = sequence.run();
+        assert_eq!(results.len(), 2);
+        // The rate-limited step is retried (once) before moving on.
+        assert_eq!(*log.borrow(), vec![0, 0, 1]);
+    }
+}
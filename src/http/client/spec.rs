@@ -0,0 +1,125 @@
+use reqwest::{header::HeaderMap, Method};
+
+use crate::http::{
+    client::{unity, Client, ClientResult, Request, Send, SetStatePayload},
+    selector::Selector,
+    state::{Duration, State, StateChange},
+};
+
+/// An operation to perform on some lights, as part of a [`RequestSpec`](struct.RequestSpec.html).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    /// Sets the given state (like [`Selected::set_state`](struct.Selected.html#method.set_state)).
+    SetState(State),
+    /// Applies the given change (like
+    /// [`Selected::change_state`](struct.Selected.html#method.change_state)).
+    ChangeState(StateChange),
+    /// Toggles power (like [`Selected::toggle`](struct.Selected.html#method.toggle)), optionally
+    /// over the given transition time.
+    Toggle(Option<Duration>),
+}
+
+/// A request which isn't (yet) tied to a client.
+///
+/// The request builders all borrow a [`Client`](struct.Client.html), which makes it impossible
+/// to describe a request before one exists. A `RequestSpec` captures just what to do (and to
+/// which lights); it can be built ahead of time, compared in tests, or (de)serialized, and is
+/// later sent with [`Client::execute`](struct.Client.html#method.execute).
+///
+/// ## Example
+/// ```
+/// use lifxi::http::{prelude::*, Operation, RequestSpec};
+/// # fn run() {
+/// let plan = vec![
+///     RequestSpec {
+///         selector: Selector::Group("Kitchen".to_string()),
+///         operation: Operation::SetState(State::builder().power(true).brightness(0.8)),
+///     },
+///     RequestSpec {
+///         selector: Selector::Label("Porch".to_string()),
+///         operation: Operation::Toggle(None),
+///     },
+/// ];
+/// let client = Client::new("foo");
+/// for spec in &plan {
+///     let result = client.execute(spec);
+/// }
+/// # }
+/// ```
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RequestSpec {
+    /// The lights upon which to act.
+    pub selector: Selector,
+    /// What to do to them.
+    pub operation: Operation,
+}
+
+impl Client {
+    /// Sends the described request using this client.
+    ///
+    /// The request is subject to [`guard_all`](struct.ClientBuilder.html#method.guard_all), like
+    /// one made with the builders; specs can't confirm requests affecting all lights.
+    pub fn execute(&self, spec: &RequestSpec) -> ClientResult {
+        let selected = self.select(spec.selector.clone());
+        match &spec.operation {
+            Operation::SetState(state) => Request {
+                client: self,
+                path: format!("/lights/{}/state", spec.selector),
+                body: SetStatePayload::new(state.clone(), None),
+                method: Method::PUT,
+                attempts: unity(),
+                backoff: None,
+                headers: HeaderMap::new(),
+                guarded: selected.guarded(),
+            }
+            .send(),
+            Operation::ChangeState(change) => Request {
+                client: self,
+                path: format!("/lights/{}/state/delta", spec.selector),
+                body: change,
+                method: Method::POST,
+                attempts: unity(),
+                backoff: None,
+                headers: HeaderMap::new(),
+                guarded: selected.guarded(),
+            }
+            .send(),
+            Operation::Toggle(None) => selected.toggle().send(),
+            Operation::Toggle(Some(duration)) => selected.toggle().transition(*duration).send(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::Color;
+    #[test]
+    fn round_trip() {
+        let spec = RequestSpec {
+            selector: Selector::Group("Kitchen".to_string()),
+            operation: Operation::SetState(State::builder().power(true).color(Color::Red)),
+        };
+        let json = serde_json::to_value(&spec).expect("Failed to serialize spec.");
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "selector": "group:Kitchen",
+                "operation": { "set_state": { "power": "on", "color": "red" } },
+            })
+        );
+        let parsed: RequestSpec = serde_json::from_value(json).expect("Failed to parse spec.");
+        assert_eq!(parsed.selector, spec.selector);
+        match parsed.operation {
+            Operation::SetState(state) => assert_eq!(state.color, Some(Color::Red)),
+            _ => panic!("Wrong operation."),
+        }
+        let json = serde_json::json!({ "selector": "all", "operation": { "toggle": 1.5 } });
+        let parsed: RequestSpec = serde_json::from_value(json).expect("Failed to parse spec.");
+        match parsed.operation {
+            Operation::Toggle(Some(duration)) => assert_eq!(duration.as_secs_f64(), 1.5),
+            _ => panic!("Wrong operation."),
+        }
+    }
+}
@@ -1,10 +1,77 @@
+use crate::common::Product;
 use crate::http::{
-    client::{unity, AsRequest, Attempts, Client, Request, Selected},
-    state::{Color, Duration, Power, State, StateChange},
+    client::{AsRequest, Attempts, Client, ClientResult, Error, Request, Retries, Selected, Send},
+    light::{Light, ProductInfo},
+    selector::{Selector, Zoned},
+    state::{Color, ColorParseError, Duration, Level, Power, State, StateChange},
     Select,
 };
-use reqwest::Method;
+use reqwest::{header::HeaderMap, Method};
+use std::fmt;
 use std::num::NonZeroU8;
+use std::time::{Duration as StdDuration, Instant};
+
+/// The transition time used by [`Selected::dim`](struct.Selected.html#method.dim) and friends.
+///
+/// Kept short so that rapid successive steps (e.g., from a rotary encoder) don't queue up long
+/// transitions on the device.
+pub const DIM_TRANSITION: StdDuration = StdDuration::from_millis(150);
+
+/// How close (reported) fractional values must be to count as unchanged when diffing states.
+const DIFF_TOLERANCE: f32 = 0.005;
+
+/// Whether the light's current color already satisfies the given color setting.
+///
+/// Only components specified by the setting are compared; colors which can't be compared (named,
+/// RGB, or custom colors) never match.
+fn color_matches(color: &Color, light: &Light) -> bool {
+    let requested = match color {
+        Color::Hue(h) => Color::Hsbk(Some(*h), None, None, None),
+        Color::Saturation(s) => Color::Hsbk(None, Some(*s), None, None),
+        Color::Brightness(b) => Color::Hsbk(None, None, Some(*b), None),
+        Color::Kelvin(k) => Color::Hsbk(None, Some(0.0), None, Some(*k)),
+        Color::Hsbk(..) => color.clone(),
+        _ => return false,
+    };
+    match &requested {
+        Color::Hsbk(h, s, b, k) => {
+            let reported = Color::Hsbk(
+                h.and(Some(light.color.hue.round() as u16 % 360)),
+                s.and(Some(light.color.saturation)),
+                b.and(Some(light.brightness)),
+                k.and(Some(light.color.kelvin)),
+            );
+            requested.approx_eq(&reported, DIFF_TOLERANCE)
+        }
+        _ => false,
+    }
+}
+
+/// Whether a light reports the given state, within tolerance.
+///
+/// Infrared levels are only compared for lights which report one; colors are compared as by
+/// `color_matches`.
+pub(crate) fn state_matches(state: &State, light: &Light) -> bool {
+    if let Some(power) = state.power {
+        if power != light.power {
+            return false;
+        }
+    }
+    if let Some(brightness) = state.brightness {
+        if (brightness - light.brightness).abs() > DIFF_TOLERANCE {
+            return false;
+        }
+    }
+    if let (Some(requested), Some(reported)) = (state.infrared, light.infrared) {
+        if (requested - reported).abs() > DIFF_TOLERANCE {
+            return false;
+        }
+    }
+    match &state.color {
+        Some(color) => color_matches(color, light),
+        None => true,
+    }
+}
 
 /// A scoped request to toggle specific lights which may be further customized.
 ///
@@ -33,14 +100,14 @@ use std::num::NonZeroU8;
 /// # }
 pub struct Toggle<'a, T: Select> {
     parent: &'a Selected<'a, T>,
-    attempts: Option<NonZeroU8>,
+    retries: Retries,
 }
 
 impl<'a, T: Select> Toggle<'a, T> {
     pub(crate) fn new(parent: &'a Selected<'a, T>) -> Self {
         Self {
             parent,
-            attempts: None,
+            retries: Retries::default(),
         }
     }
     /// Sets the transition time for the toggle.
@@ -62,14 +129,24 @@ impl<'a, T: Select> Toggle<'a, T> {
             path: format!("/lights/{}/toggle", self.parent.selector),
             body: duration.into(),
             method: Method::POST,
-            attempts: self.attempts.unwrap_or_else(unity),
+            attempts: self.retries.attempts(),
+            backoff: self.retries.backoff,
+            headers: HeaderMap::new(),
+            guarded: self.parent.guarded(),
         }
     }
+    /// An alias for [`transition`](#method.transition), reading as "toggle over two seconds."
+    pub fn over<D: Into<Duration>>(&self, duration: D) -> Request<'_, Duration> {
+        self.transition(duration)
+    }
 }
 
 impl<'a, T: Select> Attempts for Toggle<'a, T> {
     fn set_attempts(&mut self, attempts: NonZeroU8) {
-        self.attempts = Some(attempts);
+        self.retries.attempts = Some(attempts);
+    }
+    fn set_backoff(&mut self, base: StdDuration) {
+        self.retries.backoff = Some(base);
     }
 }
 
@@ -87,7 +164,13 @@ impl<'a, T: Select> AsRequest<()> for Toggle<'a, T> {
         &()
     }
     fn attempts(&self) -> NonZeroU8 {
-        self.attempts.unwrap_or_else(unity)
+        self.retries.attempts()
+    }
+    fn retry_backoff(&self) -> Option<StdDuration> {
+        self.retries.backoff
+    }
+    fn guarded(&self) -> bool {
+        self.parent.guarded()
     }
 }
 
@@ -101,6 +184,12 @@ pub struct SetStatePayload {
     fast: Option<bool>,
 }
 
+impl SetStatePayload {
+    pub(crate) fn new(state: State, fast: Option<bool>) -> Self {
+        Self { state, fast }
+    }
+}
+
 /// A scoped request to uniformly set the state for all selected bulbs.
 ///
 /// ## Example
@@ -123,8 +212,9 @@ pub struct SetStatePayload {
 /// ```
 pub struct SetState<'a, T: Select> {
     parent: &'a Selected<'a, T>,
-    attempts: Option<NonZeroU8>,
+    retries: Retries,
     payload: SetStatePayload,
+    if_connected: bool,
 }
 
 impl<'a, T: Select> SetState<'a, T> {
@@ -132,7 +222,8 @@ impl<'a, T: Select> SetState<'a, T> {
         Self {
             parent,
             payload: SetStatePayload::default(),
-            attempts: None,
+            retries: Retries::default(),
+            if_connected: false,
         }
     }
     /// Sets the power state of all selected bulbs.
@@ -171,6 +262,28 @@ impl<'a, T: Select> SetState<'a, T> {
         self.payload.state.color = Some(color);
         self
     }
+    /// Sets the color of all selected bulbs from a color string (e.g., from a config file).
+    ///
+    /// Unlike [`color`](#method.color), this can fail, so the parse error is surfaced right away
+    /// instead of at send time. Prefer the typed `Color` when the color is known in code.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), ColorParseError> {
+    /// let client = Client::new("foo");
+    /// let result = client
+    ///     .select(Selector::All)
+    ///     .set_state()
+    ///     .color_str("hue:120 saturation:50%")?
+    ///     .send();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn color_str(&mut self, color: &str) -> Result<&'_ mut Self, ColorParseError> {
+        let color = color.parse()?;
+        Ok(self.color(color))
+    }
     /// Sets the brightness of all selected bulbs (overriding color settings).
     ///
     /// ## Example
@@ -185,8 +298,8 @@ impl<'a, T: Select> SetState<'a, T> {
     ///     .send();
     /// # }
     /// ```
-    pub fn brightness(&mut self, brightness: f32) -> &'_ mut Self {
-        self.payload.state.brightness = Some(brightness);
+    pub fn brightness<L: Into<Level>>(&mut self, brightness: L) -> &'_ mut Self {
+        self.payload.state.brightness = Some(brightness.into().get());
         self
     }
     /// Sets the transition time (duration) for the change.
@@ -207,6 +320,24 @@ impl<'a, T: Select> SetState<'a, T> {
         self.payload.state.duration = Some(duration.into());
         self
     }
+    /// An alias for [`transition`](#method.transition), reading as "change over two seconds."
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() {
+    /// let client = Client::new("foo");
+    /// let result = client
+    ///     .select(Selector::All)
+    ///     .set_state()
+    ///     .brightness(0.5)
+    ///     .over(::std::time::Duration::from_secs(2))
+    ///     .send();
+    /// # }
+    /// ```
+    pub fn over<D: Into<Duration>>(&mut self, duration: D) -> &'_ mut Self {
+        self.transition(duration)
+    }
     /// Sets the infrared level, if applicable.
     ///
     /// ## Example
@@ -221,8 +352,8 @@ impl<'a, T: Select> SetState<'a, T> {
     ///     .send();
     /// # }
     /// ```
-    pub fn infrared(&mut self, ir: f32) -> &'_ mut Self {
-        self.payload.state.infrared = Some(ir);
+    pub fn infrared<L: Into<Level>>(&mut self, ir: L) -> &'_ mut Self {
+        self.payload.state.infrared = Some(ir.into().get());
         self
     }
     /// Sets whether to perform the action quickly (skipping checks and verification).
@@ -244,11 +375,118 @@ impl<'a, T: Select> SetState<'a, T> {
         self.payload.fast = Some(quickly);
         self
     }
+    /// Leaves out any requested color, brightness, or infrared level which the given light already
+    /// has, so that only what differs is sent.
+    ///
+    /// Fractional values are compared with a small tolerance (see
+    /// [`Color::approx_eq`](enum.Color.html#method.approx_eq)), and only the components a color
+    /// setting specifies are compared; named, RGB, and custom colors are always sent. An
+    /// explicitly requested power state is always sent, as is any infrared level for a light which
+    /// doesn't report one.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::new("foo");
+    /// let desk = client.select(Selector::Label("Desk".to_string()));
+    /// let current = desk.lights()?;
+    /// let mut request = desk.set_state();
+    /// request.color(Color::Kelvin(2700)).brightness(0.8);
+    /// if let Some(light) = current.first() {
+    ///     request.diff_against(light);
+    /// }
+    /// request.send()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn diff_against(&mut self, current: &Light) -> &'_ mut Self {
+        let state = &mut self.payload.state;
+        if let Some(brightness) = state.brightness {
+            if (brightness - current.brightness).abs() <= DIFF_TOLERANCE {
+                state.brightness = None;
+            }
+        }
+        if let Some(color) = &state.color {
+            if color_matches(color, current) {
+                state.color = None;
+            }
+        }
+        if let (Some(requested), Some(reported)) = (state.infrared, current.infrared) {
+            if (requested - reported).abs() <= DIFF_TOLERANCE {
+                state.infrared = None;
+            }
+        }
+        self
+    }
+    /// Asks the API to validate the requested color, if it is a custom or hex color string.
+    ///
+    /// These colors aren't checked locally, and sending an invalid one fails with
+    /// [`Error::BadRequest`](enum.Error.html#variant.BadRequest). This is an opt-in extra round
+    /// trip (via [`Client::validate`](struct.Client.html#method.validate)) for catching bad
+    /// strings (e.g., from user input) before the real request is sent; other colors are accepted
+    /// without contacting the API.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::new("foo");
+    /// let office = client.select(Selector::Label("Office".to_string()));
+    /// let mut request = office.set_state();
+    /// request.color(Color::Custom("cyan".to_string()));
+    /// request.validate_custom_colors()?;
+    /// request.send()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn validate_custom_colors(&self) -> Result<(), Error> {
+        match &self.payload.state.color {
+            Some(color @ Color::Custom(_)) | Some(color @ Color::RgbStr(_)) => {
+                self.parent.client.validate(color).send().map(|_| ())
+            }
+            _ => Ok(()),
+        }
+    }
+    /// Only sends the request to selected lights which are currently connected.
+    ///
+    /// When sending, the selected lights are listed first (costing an extra request); if some of
+    /// them are offline, the request is narrowed to the connected ones by ID, keeping any zones
+    /// they were selected with. If none of them are connected, nothing is sent, and an empty
+    /// `204 No Content` response is returned instead (for which
+    /// [`send_results`](trait.Send.html#method.send_results) reports no results). This applies to
+    /// [`send`](trait.Send.html#tymethod.send) and its retrying and asynchronous counterparts,
+    /// but not to [`request`](trait.Send.html#tymethod.request).
+    ///
+    /// The listing is a blocking request even when sending with
+    /// [`send_async`](trait.SendAsync.html#tymethod.send_async), made before the future is
+    /// returned.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() {
+    /// let client = Client::new("foo");
+    /// let result = client
+    ///     .select(Selector::Label("Flaky Lamp".to_string()))
+    ///     .set_state()
+    ///     .power(true)
+    ///     .if_connected()
+    ///     .send();
+    /// # }
+    /// ```
+    pub fn if_connected(&mut self) -> &'_ mut Self {
+        self.if_connected = true;
+        self
+    }
 }
 
 impl<'a, T: Select> Attempts for SetState<'a, T> {
     fn set_attempts(&mut self, attempts: NonZeroU8) {
-        self.attempts = Some(attempts);
+        self.retries.attempts = Some(attempts);
+    }
+    fn set_backoff(&mut self, base: StdDuration) {
+        self.retries.backoff = Some(base);
     }
 }
 
@@ -266,7 +504,148 @@ impl<'a, T: Select> AsRequest<SetStatePayload> for SetState<'a, T> {
         &self.payload
     }
     fn attempts(&self) -> NonZeroU8 {
-        self.attempts.unwrap_or_else(unity)
+        self.retries.attempts()
+    }
+    fn retry_backoff(&self) -> Option<StdDuration> {
+        self.retries.backoff
+    }
+    fn guarded(&self) -> bool {
+        self.parent.guarded()
+    }
+    fn preflight(&self) -> Result<Option<String>, Error> {
+        // A guarded request will be refused anyway, so don't list the lights for it.
+        if !self.if_connected || self.guarded() {
+            return Ok(Some(self.path()));
+        }
+        // Connectivity changes quickly, so don't trust the cache with it.
+        let lights = self.parent.list_fresh()?;
+        let connected = lights.iter().filter(|l| l.connected).collect::<Vec<_>>();
+        if connected.is_empty() {
+            return Ok(None);
+        }
+        let selector = &self.parent.selector;
+        // A randomized selection may pick a different light next time, so pin the one we listed.
+        if connected.len() < lights.len() || selector.is_random() {
+            if let Some(narrowed) = narrow(selector, &connected) {
+                return Ok(Some(format!("/lights/{}/state", narrowed)));
+            }
+        }
+        Ok(Some(self.path()))
+    }
+}
+
+/// Narrows the selection to the given lights by ID, keeping the zones each light was selected
+/// with, or returns `None` if the selector can't be broken down that way.
+fn narrow<T: Select>(selector: &T, lights: &[&Light]) -> Option<String> {
+    let parts = selector.parts();
+    if parts.is_empty()
+        || parts
+            .iter()
+            .any(|part| part.selector().kind() == "scene_id")
+    {
+        return None;
+    }
+    let mut narrowed = Vec::new();
+    for light in lights {
+        for part in parts
+            .iter()
+            .filter(|part| light.matched_by(part.selector()))
+        {
+            let id = Selector::Id(light.id.clone()).zoned(part.zones().clone());
+            let id = format!("{}", id);
+            if !narrowed.contains(&id) {
+                narrowed.push(id);
+            }
+        }
+    }
+    if narrowed.is_empty() {
+        None
+    } else {
+        Some(narrowed.join(","))
+    }
+}
+
+/// A scoped request to turn all selected bulbs on or off, leaving everything else untouched.
+///
+/// This sends the same request as [`SetState`](struct.SetState.html), with only the power state
+/// (and, optionally, the transition time and fast mode) set.
+///
+/// ## Example
+/// ```
+/// use lifxi::http::prelude::*;
+/// # fn run() {
+/// let client = Client::new("foo");
+/// let result = client
+///     .select(Selector::Group("Office".to_string()))
+///     .power(false)
+///     .transition(::std::time::Duration::new(3, 0))
+///     .send();
+/// # }
+/// ```
+pub struct SetPower<'a, T: Select> {
+    parent: &'a Selected<'a, T>,
+    retries: Retries,
+    payload: SetStatePayload,
+}
+
+impl<'a, T: Select> SetPower<'a, T> {
+    pub(crate) fn new(parent: &'a Selected<'a, T>, on: bool) -> Self {
+        let state = State {
+            power: Some(on.into()),
+            ..State::default()
+        };
+        Self {
+            parent,
+            retries: Retries::default(),
+            payload: SetStatePayload::new(state, None),
+        }
+    }
+    /// Sets the transition time for the power change.
+    pub fn transition<D: Into<Duration>>(&mut self, duration: D) -> &'_ mut Self {
+        self.payload.state.duration = Some(duration.into());
+        self
+    }
+    /// An alias for [`transition`](#method.transition), reading as "turn off over three seconds."
+    pub fn over<D: Into<Duration>>(&mut self, duration: D) -> &'_ mut Self {
+        self.transition(duration)
+    }
+    /// Sets whether to perform the action quickly (skipping checks and verification).
+    pub fn fast(&mut self, quickly: bool) -> &'_ mut Self {
+        self.payload.fast = Some(quickly);
+        self
+    }
+}
+
+impl<'a, T: Select> Attempts for SetPower<'a, T> {
+    fn set_attempts(&mut self, attempts: NonZeroU8) {
+        self.retries.attempts = Some(attempts);
+    }
+    fn set_backoff(&mut self, base: StdDuration) {
+        self.retries.backoff = Some(base);
+    }
+}
+
+impl<'a, T: Select> AsRequest<SetStatePayload> for SetPower<'a, T> {
+    fn method() -> reqwest::Method {
+        Method::PUT
+    }
+    fn client(&self) -> &'_ Client {
+        self.parent.client
+    }
+    fn path(&self) -> String {
+        format!("/lights/{}/state", self.parent.selector)
+    }
+    fn body(&self) -> &'_ SetStatePayload {
+        &self.payload
+    }
+    fn attempts(&self) -> NonZeroU8 {
+        self.retries.attempts()
+    }
+    fn retry_backoff(&self) -> Option<StdDuration> {
+        self.retries.backoff
+    }
+    fn guarded(&self) -> bool {
+        self.parent.guarded()
     }
 }
 
@@ -275,6 +654,9 @@ struct StateExt {
     pub(crate) selector: String,
     #[serde(flatten)]
     pub(crate) state: State,
+    /// Whether the selector matches every light on the account.
+    #[serde(skip)]
+    pub(crate) targets_all: bool,
 }
 
 #[derive(Clone, Default, Serialize)]
@@ -310,7 +692,8 @@ pub struct SetStatesPayload {
 pub struct SetStates<'a> {
     parent: &'a Client,
     inner: SetStatesPayload,
-    attempts: Option<NonZeroU8>,
+    confirmed: bool,
+    retries: Retries,
 }
 
 impl<'a> SetStates<'a> {
@@ -318,18 +701,66 @@ impl<'a> SetStates<'a> {
         Self {
             parent,
             inner: SetStatesPayload::default(),
-            attempts: None,
+            confirmed: false,
+            retries: Retries::default(),
         }
     }
+    /// Confirms that entries in this request may affect every light on the account.
+    ///
+    /// Only meaningful for clients built with
+    /// [`ClientBuilder::guard_all`](struct.ClientBuilder.html#method.guard_all); a no-op otherwise.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::builder("foo").guard_all(true).build()?;
+    /// let result = client
+    ///     .set_states()
+    ///     .add(Selector::All, State::builder().power(false))
+    ///     .confirm_all()
+    ///     .send();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn confirm_all(&mut self) -> &'_ mut Self {
+        self.confirmed = true;
+        self
+    }
     /// Adds the given state to the list.
     #[allow(clippy::needless_pass_by_value)]
     pub fn add<T: Select>(&mut self, selector: T, state: State) -> &'_ mut Self {
         self.inner.new.push(StateExt {
             selector: format!("{}", selector),
             state,
+            targets_all: selector.targets_all(),
         });
         self
     }
+    /// Adds each of the given states to the list.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// use std::collections::HashMap;
+    /// # fn run() {
+    /// let client = Client::new("foo");
+    /// let mut rooms = HashMap::new();
+    /// rooms.insert(Selector::Group("Kitchen".to_string()), State::builder().power(true));
+    /// rooms.insert(Selector::Group("Bedroom".to_string()), State::builder().power(false));
+    /// let result = client.set_states().add_all(rooms).send();
+    /// # }
+    /// ```
+    pub fn add_all<T, I>(&mut self, states: I) -> &'_ mut Self
+    where
+        T: Select,
+        I: IntoIterator<Item = (T, State)>,
+    {
+        for (selector, state) in states {
+            self.add(selector, state);
+        }
+        self
+    }
     /// Sets the default properties to use if left unspecified.
     pub fn default(&mut self, state: State) -> &'_ mut Self {
         self.inner.default = Some(state);
@@ -340,11 +771,109 @@ impl<'a> SetStates<'a> {
         self.inner.fast = Some(fast);
         self
     }
+    /// Sends the request, leaving out lights which are currently offline.
+    ///
+    /// All lights are listed first (costing an extra request); any entry whose selector matches
+    /// a mix of connected and disconnected lights is narrowed to the connected ones by ID, and
+    /// entries matching only disconnected lights are dropped. This avoids waiting on doomed
+    /// requests to unplugged bulbs. Returns `Ok(None)` if every entry was dropped, in which case
+    /// nothing is sent.
+    ///
+    /// ## Notes
+    /// Combined, randomized, and scene selectors (and selectors matching no listed lights) are
+    /// sent unchanged.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() {
+    /// let client = Client::new("foo");
+    /// let result = client
+    ///     .set_states()
+    ///     .add(Selector::Group("Bedroom".to_string()), State::builder().power(true))
+    ///     .add(Selector::Label("Kitchen".to_string()), State::builder().power(true))
+    ///     .send_skip_offline();
+    /// # }
+    /// ```
+    pub fn send_skip_offline(&self) -> Result<Option<reqwest::Response>, Error> {
+        if self.guarded() {
+            // Refuse before listing the lights, so that nothing is sent.
+            return Err(Error::UnconfirmedAll);
+        }
+        let all = self.parent.select(Selector::All);
+        // Connectivity changes quickly, so don't trust the cache with it.
+        let lights = all.list_fresh()?;
+        let mut payload = self.inner.clone();
+        payload.new = payload
+            .new
+            .into_iter()
+            .filter_map(|entry| skip_offline(entry, &lights))
+            .collect();
+        if payload.new.is_empty() {
+            return Ok(None);
+        }
+        let request = Request {
+            client: self.parent,
+            path: self.path(),
+            body: payload,
+            method: Self::method(),
+            attempts: self.attempts(),
+            backoff: self.retry_backoff(),
+            headers: HeaderMap::new(),
+            guarded: self.guarded(),
+        };
+        request.send().map(Some)
+    }
+}
+
+/// Narrows the entry to the connected lights it matches, or drops it if they're all offline.
+fn skip_offline(entry: StateExt, lights: &[Light]) -> Option<StateExt> {
+    let selector = &entry.selector;
+    if selector.contains(',') || selector.ends_with(":random") {
+        return Some(entry);
+    }
+    let zoned = match selector.parse::<Zoned>() {
+        Ok(zoned) => zoned,
+        Err(_) => return Some(entry),
+    };
+    let matching = lights
+        .iter()
+        .filter(|light| light.matched_by(zoned.selector()))
+        .collect::<Vec<_>>();
+    if matching.iter().all(|light| light.connected) {
+        return Some(entry);
+    }
+    let connected = matching
+        .into_iter()
+        .filter(|light| light.connected)
+        .map(|light| {
+            let id = Selector::Id(light.id.clone());
+            format!("{}", id.zoned(zoned.zones().clone()))
+        })
+        .collect::<Vec<_>>();
+    if connected.is_empty() {
+        None
+    } else {
+        Some(StateExt {
+            selector: connected.join(","),
+            state: entry.state,
+            targets_all: entry.targets_all,
+        })
+    }
+}
+
+impl<'a, T: Select> Extend<(T, State)> for SetStates<'a> {
+    fn extend<I: IntoIterator<Item = (T, State)>>(&mut self, states: I) {
+        self.add_all(states);
+    }
 }
 
 impl<'a> Attempts for SetStates<'a> {
     fn set_attempts(&mut self, attempts: NonZeroU8) {
-        self.attempts = Some(attempts);
+        self.retries.attempts = Some(attempts);
+    }
+    fn set_backoff(&mut self, base: StdDuration) {
+        self.retries.backoff = Some(base);
     }
 }
 
@@ -362,7 +891,171 @@ impl<'a> AsRequest<SetStatesPayload> for SetStates<'a> {
         &self.inner
     }
     fn attempts(&self) -> NonZeroU8 {
-        self.attempts.unwrap_or_else(unity)
+        self.retries.attempts()
+    }
+    fn retry_backoff(&self) -> Option<StdDuration> {
+        self.retries.backoff
+    }
+    fn guarded(&self) -> bool {
+        self.parent.guard_all
+            && !self.confirmed
+            && self.inner.new.iter().any(|entry| entry.targets_all)
+    }
+}
+
+/// The most entries the API accepts in a single `set_states` request.
+const MAX_STATES: usize = 50;
+
+/// Represents a (local) zone validation error.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ZoneValidationError {
+    /// More colors were given than there are addressable zones (256). The number of colors is
+    /// returned.
+    TooManyZones(usize),
+    /// A selected light is a known product without zones. The product is returned.
+    NotMultizone(Product),
+}
+
+impl fmt::Display for ZoneValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::TooManyZones(n) => {
+                write!(f, "{} zone colors given; at most 256 zones exist.", n)
+            }
+            Self::NotMultizone(product) => {
+                write!(f, "{:?} does not support multizoning.", product)
+            }
+        }
+    }
+}
+
+impl ::std::error::Error for ZoneValidationError {}
+
+/// Checks that none of the given lights are known products without zones.
+///
+/// Lights whose product isn't known are given the benefit of the doubt.
+fn check_multizone(lights: &[Light]) -> Result<(), ZoneValidationError> {
+    let products = lights
+        .iter()
+        .filter_map(|light| light.product.as_ref().and_then(ProductInfo::product));
+    for product in products {
+        if !product.multizone() {
+            return Err(ZoneValidationError::NotMultizone(product));
+        }
+    }
+    Ok(())
+}
+
+/// A scoped request to set the color of each zone of the selected multizone light(s).
+///
+/// Consecutive zones sharing a color are sent as a single entry. If the colors still need more
+/// entries than the API accepts in one request (50), they're split across several requests, sent
+/// in order.
+///
+/// ## Notes
+/// The HTTP API applies zone changes immediately; the staged "apply" modes of the LAN protocol
+/// are not exposed. A gradient split across several requests is therefore applied in steps.
+///
+/// ## Example
+/// ```
+/// use lifxi::http::prelude::*;
+/// # fn run() {
+/// let client = Client::new("foo");
+/// let result = client
+///     .select(Selector::Label("Strip".to_string()))
+///     .set_zones(vec![Color::Red, Color::Orange, Color::Yellow])
+///     .send();
+/// # }
+/// ```
+pub struct SetZones<'a> {
+    parent: &'a Selected<'a, Selector>,
+    colors: Vec<Color>,
+    retries: Retries,
+}
+
+impl<'a> SetZones<'a> {
+    pub(crate) fn new(parent: &'a Selected<'a, Selector>, colors: Vec<Color>) -> Self {
+        Self {
+            parent,
+            colors,
+            retries: Retries::default(),
+        }
+    }
+    /// Checks the request locally, without sending anything.
+    ///
+    /// Fails if more than 256 colors were given, or if any selected light is a known product
+    /// without zones (see [`Product::multizone`](../common/enum.Product.html#method.multizone)).
+    /// Products are only known if the selected lights are in the client's
+    /// [cache](struct.ClientBuilder.html#method.cache_ttl); no request is made to find out.
+    pub fn validate(&self) -> Result<(), ZoneValidationError> {
+        if self.colors.len() > 256 {
+            return Err(ZoneValidationError::TooManyZones(self.colors.len()));
+        }
+        let cached = self
+            .parent
+            .client
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.get(&self.parent.selector.to_string()));
+        match cached {
+            Some(lights) => check_multizone(&lights),
+            None => Ok(()),
+        }
+    }
+    /// The payloads to send, in order.
+    fn payloads(&self) -> Vec<SetStatesPayload> {
+        let selector = &self.parent.selector;
+        let mut runs: Vec<(&Color, Vec<u8>)> = Vec::new();
+        for (zone, color) in (0..=255).zip(&self.colors) {
+            match runs.last_mut() {
+                Some((last, zones)) if last.to_string() == color.to_string() => zones.push(zone),
+                _ => runs.push((color, vec![zone])),
+            }
+        }
+        runs.chunks(MAX_STATES)
+            .map(|chunk| SetStatesPayload {
+                new: chunk
+                    .iter()
+                    .map(|(color, zones)| StateExt {
+                        selector: format!("{}", selector.clone().zoned(zones.clone())),
+                        state: State::builder().color(Color::clone(color)),
+                        targets_all: selector.targets_all(),
+                    })
+                    .collect(),
+                ..SetStatesPayload::default()
+            })
+            .collect()
+    }
+    /// Validates and sends the request(s), stopping at the first failure.
+    ///
+    /// One response is returned per request sent.
+    pub fn send(&self) -> Result<Vec<reqwest::Response>, Error> {
+        self.validate().map_err(Error::InvalidZones)?;
+        self.payloads()
+            .into_iter()
+            .map(|payload| {
+                let request = Request {
+                    client: self.parent.client,
+                    path: "/lights/states".to_string(),
+                    body: payload,
+                    method: Method::PUT,
+                    attempts: self.retries.attempts(),
+                    backoff: self.retries.backoff,
+                    headers: HeaderMap::new(),
+                    guarded: self.parent.guarded(),
+                };
+                request.send()
+            })
+            .collect()
+    }
+}
+
+impl<'a> Attempts for SetZones<'a> {
+    fn set_attempts(&mut self, attempts: NonZeroU8) {
+        self.retries.attempts = Some(attempts);
+    }
+    fn set_backoff(&mut self, base: StdDuration) {
+        self.retries.backoff = Some(base);
     }
 }
 
@@ -389,7 +1082,7 @@ impl<'a> AsRequest<SetStatesPayload> for SetStates<'a> {
 pub struct ChangeState<'a, T: Select> {
     parent: &'a Selected<'a, T>,
     change: StateChange,
-    attempts: Option<NonZeroU8>,
+    retries: Retries,
 }
 
 impl<'a, T: Select> ChangeState<'a, T> {
@@ -397,7 +1090,7 @@ impl<'a, T: Select> ChangeState<'a, T> {
         Self {
             parent,
             change: StateChange::default(),
-            attempts: None,
+            retries: Retries::default(),
         }
     }
     /// Sets target power state.
@@ -438,6 +1131,24 @@ impl<'a, T: Select> ChangeState<'a, T> {
         self.change.duration = Some(duration.into());
         self
     }
+    /// An alias for [`transition`](#method.transition), reading as "change over two seconds."
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() {
+    /// let client = Client::new("foo");
+    /// let result = client
+    ///     .select(Selector::All)
+    ///     .change_state()
+    ///     .brightness(-0.2)
+    ///     .over(::std::time::Duration::from_secs(2))
+    ///     .send();
+    /// # }
+    /// ```
+    pub fn over<D: Into<Duration>>(&mut self, duration: D) -> &'_ mut Self {
+        self.transition(duration)
+    }
     /// Sets change in hue.
     ///
     /// ## Example
@@ -534,7 +1245,10 @@ impl<'a, T: Select> ChangeState<'a, T> {
 
 impl<'a, T: Select> Attempts for ChangeState<'a, T> {
     fn set_attempts(&mut self, attempts: NonZeroU8) {
-        self.attempts = Some(attempts);
+        self.retries.attempts = Some(attempts);
+    }
+    fn set_backoff(&mut self, base: StdDuration) {
+        self.retries.backoff = Some(base);
     }
 }
 
@@ -552,7 +1266,84 @@ impl<'a, T: Select> AsRequest<StateChange> for ChangeState<'a, T> {
         &self.change
     }
     fn attempts(&self) -> NonZeroU8 {
-        self.attempts.unwrap_or_else(unity)
+        self.retries.attempts()
+    }
+    fn retry_backoff(&self) -> Option<StdDuration> {
+        self.retries.backoff
+    }
+    fn guarded(&self) -> bool {
+        self.parent.guarded()
+    }
+}
+
+/// Coalesces rapid state changes (e.g., from a slider) into fewer requests.
+///
+/// Created by [`Client::debounced`](struct.Client.html#method.debounced). Changes passed to
+/// [`change_state`](#method.change_state) are summed (see
+/// [`StateChange`'s `Add` impl](struct.StateChange.html#impl-Add)) rather than sent; once the
+/// window has elapsed since the first pending change, the sum is sent as a single request.
+///
+/// ## Latency
+/// No background thread is involved: the pending change is sent by the first call made at or
+/// after the end of the window, so changes may wait up to a full window (or longer, if input
+/// stops) before being sent. Call [`flush`](#method.flush) when input ends (e.g., when a slider
+/// is released) so that the last changes aren't left pending. Longer windows send fewer requests
+/// but feel less responsive.
+///
+/// ## Example
+/// ```
+/// use lifxi::http::prelude::*;
+/// use std::time::Duration;
+/// # fn run() {
+/// let client = Client::new("foo");
+/// let mut slider = client.debounced(Selector::Group("Lounge".to_string()), Duration::from_millis(200));
+/// for _ in 0..10 {
+///     slider.change_state(StateChange::builder().brightness(0.05));
+/// }
+/// let result = slider.flush();
+/// # }
+/// ```
+pub struct Debounced<'a, T: Select> {
+    selected: Selected<'a, T>,
+    window: StdDuration,
+    pending: Option<(Instant, StateChange)>,
+}
+
+impl<'a, T: Select> Debounced<'a, T> {
+    pub(crate) fn new(selected: Selected<'a, T>, window: StdDuration) -> Self {
+        Self {
+            selected,
+            window,
+            pending: None,
+        }
+    }
+    /// Queues a change, sending the accumulated change if the window has elapsed.
+    ///
+    /// Returns the result of sending, if anything was sent.
+    pub fn change_state(&mut self, change: StateChange) -> Option<ClientResult> {
+        let (started, pending) = match self.pending.take() {
+            Some((started, pending)) => (started, pending + change),
+            None => (Instant::now(), change),
+        };
+        self.pending = Some((started, pending));
+        if started.elapsed() >= self.window {
+            self.flush()
+        } else {
+            None
+        }
+    }
+    /// The accumulated change which has yet to be sent, if any.
+    pub fn pending(&self) -> Option<&StateChange> {
+        self.pending.as_ref().map(|(_, change)| change)
+    }
+    /// Sends the accumulated change immediately, regardless of the window.
+    ///
+    /// Returns the result of sending, or `None` if nothing was pending.
+    pub fn flush(&mut self) -> Option<ClientResult> {
+        let (_, change) = self.pending.take()?;
+        let mut request = ChangeState::new(&self.selected);
+        request.change = change;
+        Some(request.send())
     }
 }
 
@@ -586,7 +1377,7 @@ impl<'a, T: Select> AsRequest<StateChange> for ChangeState<'a, T> {
 pub struct Cycle<'a, T: Select> {
     parent: &'a Selected<'a, T>,
     inner: CyclePayload<'a, T>,
-    attempts: Option<NonZeroU8>,
+    retries: Retries,
 }
 
 impl<'a, T: Select> Cycle<'a, T> {
@@ -594,7 +1385,7 @@ impl<'a, T: Select> Cycle<'a, T> {
         Self {
             parent,
             inner: CyclePayload::new(&parent.selector),
-            attempts: None,
+            retries: Retries::default(),
         }
     }
     /// Adds a state to the cycle.
@@ -603,27 +1394,95 @@ impl<'a, T: Select> Cycle<'a, T> {
         self
     }
     /// Sets the default values to use when not specified.
+    ///
+    /// Defaults only fill in fields which a state in the cycle leaves unset (`None`); any field
+    /// set on an individual state takes precedence. In particular, a default power state applies
+    /// to every state which doesn't set its own, so states meant to leave power alone should
+    /// only be paired with defaults which don't set it either.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() {
+    /// let client = Client::new("foo");
+    /// let result = client
+    ///     .select(Selector::All)
+    ///     .cycle()
+    ///     .add(State::builder().color(Color::Red))
+    ///     // Overrides the default brightness for this state only.
+    ///     .add(State::builder().color(Color::Blue).brightness(1.0))
+    ///     .default(State::builder().brightness(0.5))
+    ///     .send();
+    /// # }
+    /// ```
     pub fn default(&mut self, state: State) -> &'_ mut Self {
         self.inner.default = Some(state);
         self
     }
     /// Reverses the direction of the cycle.
+    ///
+    /// Cycles move forward by default; calling this twice restores the original direction. To set
+    /// the direction explicitly, use [`direction`](#method.direction) instead.
     pub fn rev(&mut self) -> &'_ mut Self {
-        self.inner.direction = if self.inner.direction == "forward" {
-            "backward"
-        } else {
-            "forward"
-        };
+        self.inner.direction = self.inner.direction.reversed();
+        self
+    }
+    /// Sets the direction of the cycle.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::{prelude::*, Direction};
+    /// # fn run() {
+    /// let client = Client::new("foo");
+    /// let result = client
+    ///     .select(Selector::All)
+    ///     .cycle()
+    ///     .add(State::builder().color(Color::Red))
+    ///     .add(State::builder().color(Color::Green))
+    ///     .direction(Direction::Backward)
+    ///     .send();
+    /// # }
+    /// ```
+    pub fn direction(&mut self, direction: Direction) -> &'_ mut Self {
+        self.inner.direction = direction;
         self
     }
 }
 
+/// The direction in which to move through a sequence of states (for a
+/// [`Cycle`](struct.Cycle.html)) or along a strip (for a [`Move`](struct.Move.html) effect, where
+/// forward moves towards the end of the strip).
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// Move forward through the sequence.
+    Forward,
+    /// Move backward through the sequence.
+    Backward,
+}
+
+impl Default for Direction {
+    fn default() -> Self {
+        Self::Forward
+    }
+}
+
+impl Direction {
+    /// Gives the opposite direction.
+    pub fn reversed(self) -> Self {
+        match self {
+            Self::Forward => Self::Backward,
+            Self::Backward => Self::Forward,
+        }
+    }
+}
+
 #[derive(Clone, Serialize)]
 #[doc(hidden)]
 /// The message constructed by the `Cycle` request builder.
 pub struct CyclePayload<'a, T: Select> {
     pub(crate) selector: &'a T,
-    pub(crate) direction: &'static str,
+    pub(crate) direction: Direction,
     pub(crate) states: Vec<State>,
     #[serde(rename = "defaults", skip_serializing_if = "Option::is_none")]
     pub(crate) default: Option<State>,
@@ -633,7 +1492,7 @@ impl<'a, T: Select> CyclePayload<'a, T> {
     fn new(selector: &'a T) -> Self {
         Self {
             selector,
-            direction: "forward",
+            direction: Direction::default(),
             states: Vec::new(),
             default: None,
         }
@@ -642,7 +1501,10 @@ impl<'a, T: Select> CyclePayload<'a, T> {
 
 impl<'a, T: Select> Attempts for Cycle<'a, T> {
     fn set_attempts(&mut self, attempts: NonZeroU8) {
-        self.attempts = Some(attempts);
+        self.retries.attempts = Some(attempts);
+    }
+    fn set_backoff(&mut self, base: StdDuration) {
+        self.retries.backoff = Some(base);
     }
 }
 
@@ -660,6 +1522,398 @@ impl<'a, T: Select> AsRequest<CyclePayload<'a, T>> for Cycle<'a, T> {
         &self.inner
     }
     fn attempts(&self) -> NonZeroU8 {
-        self.attempts.unwrap_or_else(unity)
+        self.retries.attempts()
+    }
+    fn retry_backoff(&self) -> Option<StdDuration> {
+        self.retries.backoff
+    }
+    fn guarded(&self) -> bool {
+        self.parent.guarded()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{Combine, Randomize, Send};
+    #[test]
+    fn debounced() {
+        let client = Client::new("foo");
+        let mut slider = client.debounced(Selector::All, StdDuration::from_secs(3600));
+        assert!(slider.pending().is_none());
+        assert!(slider
+            .change_state(StateChange::builder().brightness(0.1))
+            .is_none());
+        assert!(slider
+            .change_state(StateChange::builder().brightness(0.2).hue(10))
+            .is_none());
+        let pending = slider.pending().expect("Nothing pending.");
+        assert!((pending.brightness.expect("No brightness.") - 0.3).abs() < 1e-6);
+        assert_eq!(pending.hue, Some(10));
+    }
+    #[test]
+    fn cycle_payload() {
+        let client = Client::new("foo");
+        let selected = client.select(Selector::All);
+        let mut cycle = selected.cycle();
+        cycle
+            .add(State::builder().color(Color::Red))
+            .add(State::builder().color(Color::Blue).power(false));
+        let json = serde_json::to_value(cycle.body()).expect("Failed to serialize cycle.");
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "selector": "all",
+                "direction": "forward",
+                "states": [{ "color": "red" }, { "color": "blue", "power": "off" }],
+            })
+        );
+        cycle.default(State::builder().power(true).brightness(0.5));
+        let json = serde_json::to_value(cycle.body()).expect("Failed to serialize cycle.");
+        // Defaults are sent separately; per-state fields (like the second power) aren't touched.
+        assert_eq!(
+            json["defaults"],
+            serde_json::json!({ "power": "on", "brightness": 0.5 })
+        );
+        assert_eq!(json["states"][0], serde_json::json!({ "color": "red" }));
+        assert_eq!(json["states"][1]["power"], "off");
+    }
+    #[test]
+    fn cycle_direction() {
+        let client = Client::new("foo");
+        let selected = client.select(Selector::All);
+        let mut cycle = selected.cycle();
+        assert_eq!(cycle.inner.direction, Direction::Forward);
+        cycle.rev();
+        assert_eq!(cycle.inner.direction, Direction::Backward);
+        cycle.rev();
+        assert_eq!(cycle.inner.direction, Direction::Forward);
+        cycle.direction(Direction::Backward);
+        assert_eq!(cycle.inner.direction, Direction::Backward);
+        cycle.direction(Direction::Backward).rev();
+        assert_eq!(cycle.inner.direction, Direction::Forward);
+        cycle.rev().direction(Direction::Forward);
+        assert_eq!(cycle.inner.direction, Direction::Forward);
+        let json = serde_json::to_value(&cycle.inner).expect("Failed to serialize cycle.");
+        assert_eq!(json["direction"], "forward");
+        cycle.rev();
+        let json = serde_json::to_value(&cycle.inner).expect("Failed to serialize cycle.");
+        assert_eq!(json["direction"], "backward");
+    }
+    #[test]
+    fn set_power() {
+        let client = Client::new("foo");
+        let selected = client.select(Selector::All);
+        let request = selected.set_power(true, None);
+        let json = serde_json::to_value(request.body()).expect("Failed to serialize state.");
+        assert_eq!(json, serde_json::json!({ "power": "on" }));
+        let request = selected.set_power(false, Some(StdDuration::from_secs(2)));
+        let json = serde_json::to_value(request.body()).expect("Failed to serialize state.");
+        assert_eq!(json, serde_json::json!({ "power": "off", "duration": 2.0 }));
+    }
+    #[test]
+    fn power() {
+        let client = Client::new("foo");
+        let selected = client.select(Selector::All);
+        let mut request = selected.power(false);
+        let json = serde_json::to_value(request.body()).expect("Failed to serialize state.");
+        assert_eq!(json, serde_json::json!({ "power": "off" }));
+        request.transition(StdDuration::from_secs(3));
+        let json = serde_json::to_value(request.body()).expect("Failed to serialize state.");
+        assert_eq!(json, serde_json::json!({ "power": "off", "duration": 3.0 }));
+        request.fast(true);
+        let json = serde_json::to_value(request.body()).expect("Failed to serialize state.");
+        assert_eq!(
+            json,
+            serde_json::json!({ "power": "off", "duration": 3.0, "fast": true })
+        );
+        assert_eq!(request.path(), "/lights/all/state");
+    }
+    #[test]
+    fn diff_against() {
+        let light: Light = serde_json::from_str(
+            r#"{
+                "id": "a", "uuid": "", "label": "Desk", "connected": true,
+                "power": "on", "brightness": 0.8,
+                "color": { "hue": 120.0, "saturation": 0.0, "kelvin": 2700 },
+                "group": { "id": "g", "name": "Lounge" },
+                "location": { "id": "l", "name": "Home" }
+            }"#,
+        )
+        .expect("Failed to parse light.");
+        let client = Client::new("foo");
+        let selected = client.select(Selector::All);
+        let mut request = selected.set_state();
+        request
+            .power(true)
+            .color(Color::Kelvin(2700))
+            .brightness(0.801)
+            .diff_against(&light);
+        assert_eq!(request.payload.state.power, Some(Power::from(true)));
+        assert!(request.payload.state.color.is_none());
+        assert!(request.payload.state.brightness.is_none());
+        let mut request = selected.set_state();
+        request
+            .color(Color::Hsbk(Some(120), Some(0.5), None, None))
+            .brightness(0.5)
+            .diff_against(&light);
+        assert!(request.payload.state.color.is_some());
+        assert_eq!(request.payload.state.brightness, Some(0.5));
+        let mut request = selected.set_state();
+        request.color(Color::Hue(120)).diff_against(&light);
+        assert!(request.payload.state.color.is_none());
+        request.color(Color::Green).diff_against(&light);
+        assert!(request.payload.state.color.is_some());
+        let mut request = selected.set_state();
+        request.infrared(0.5).diff_against(&light);
+        assert_eq!(request.payload.state.infrared, Some(0.5));
+        let light = Light {
+            infrared: Some(0.5),
+            ..light
+        };
+        request.diff_against(&light);
+        assert!(request.payload.state.infrared.is_none());
+        request.infrared(0.7).diff_against(&light);
+        assert_eq!(request.payload.state.infrared, Some(0.7));
+    }
+    #[test]
+    fn skip_offline() {
+        let light = |id: &str, label: &str, connected: bool| {
+            let json = format!(
+                r#"{{
+                    "id": "{}", "uuid": "", "label": "{}", "connected": {},
+                    "power": "on", "brightness": 1.0,
+                    "color": {{ "hue": 0.0, "saturation": 0.0, "kelvin": 3500 }},
+                    "group": {{ "id": "g", "name": "Lounge" }},
+                    "location": {{ "id": "l", "name": "Home" }}
+                }}"#,
+                id, label, connected
+            );
+            serde_json::from_str::<Light>(&json).expect("Failed to parse light.")
+        };
+        let lights = vec![
+            light("a", "Desk", true),
+            light("b", "Ceiling", false),
+            light("c", "Strip", true),
+        ];
+        let entry = |selector: &str| StateExt {
+            selector: selector.to_string(),
+            state: State::new(),
+            targets_all: false,
+        };
+        let selector = |entry: Option<StateExt>| entry.map(|e| e.selector);
+        let skip = |s: &str| selector(super::skip_offline(entry(s), &lights));
+        assert_eq!(skip("label:Desk"), Some("label:Desk".to_string()));
+        assert_eq!(skip("label:Ceiling"), None);
+        assert_eq!(skip("group:Lounge"), Some("id:a,id:c".to_string()));
+        assert_eq!(skip("all|1|2"), Some("id:a|1|2,id:c|1|2".to_string()));
+        assert_eq!(skip("all:random"), Some("all:random".to_string()));
+        assert_eq!(skip("scene_id:abc"), Some("scene_id:abc".to_string()));
+        assert_eq!(
+            skip("label:Ceiling,label:Desk"),
+            Some("label:Ceiling,label:Desk".to_string())
+        );
+    }
+    #[test]
+    fn narrow() {
+        let light = |id: &str, label: &str| {
+            let json = format!(
+                r#"{{
+                    "id": "{}", "uuid": "", "label": "{}", "connected": true,
+                    "power": "on", "brightness": 1.0,
+                    "color": {{ "hue": 0.0, "saturation": 0.0, "kelvin": 3500 }},
+                    "group": {{ "id": "g", "name": "Lounge" }},
+                    "location": {{ "id": "l", "name": "Home" }}
+                }}"#,
+                id, label
+            );
+            serde_json::from_str::<Light>(&json).expect("Failed to parse light.")
+        };
+        let (a, b) = (light("a", "Desk"), light("b", "Strip"));
+        let lights = vec![&a, &b];
+        let desk = Selector::Label("Desk".to_string());
+        let strip = Selector::Label("Strip".to_string());
+        let lounge = Selector::Group("Lounge".to_string());
+        assert_eq!(super::narrow(&desk, &lights), Some("id:a".to_string()));
+        assert_eq!(
+            super::narrow(&lounge.clone().zoned(vec![1, 2]), &lights),
+            Some("id:a|1|2,id:b|1|2".to_string())
+        );
+        let combined = desk.clone().zoned(1).combine(strip.zoned(2));
+        assert_eq!(
+            super::narrow(&combined, &lights),
+            Some("id:a|1,id:b|2".to_string())
+        );
+        let overlapping = desk.clone().combine(lounge.clone());
+        assert_eq!(
+            super::narrow(&overlapping, &lights),
+            Some("id:a,id:b".to_string())
+        );
+        assert_eq!(
+            super::narrow(&lounge.zoned(3).random(), &[&b]),
+            Some("id:b|3".to_string())
+        );
+        assert_eq!(super::narrow(&desk, &[&b]), None);
+        let scene = Selector::SceneId("abc".to_string());
+        assert_eq!(super::narrow(&scene, &lights), None);
+    }
+    #[test]
+    fn add_all() {
+        let client = Client::new("foo");
+        let mut states = client.set_states();
+        states.add_all(vec![
+            (
+                Selector::Label("Desk".to_string()),
+                State::new().power(true),
+            ),
+            (
+                Selector::Label("Ceiling".to_string()),
+                State::new().power(false),
+            ),
+        ]);
+        states.extend(Some((Selector::All.zoned(1), State::new().brightness(0.5))));
+        let json = serde_json::to_value(&states.inner).expect("Failed to serialize states.");
+        let selectors = json["states"]
+            .as_array()
+            .expect("States weren't serialized as an array.")
+            .iter()
+            .map(|state| state["selector"].as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            selectors,
+            vec![Some("label:Desk"), Some("label:Ceiling"), Some("all|1")]
+        );
+    }
+    #[test]
+    fn guard_all() {
+        let client = Client::builder("foo")
+            .guard_all(true)
+            .build()
+            .expect("Failed to build client.");
+        let all = client.select(Selector::All);
+        match all.toggle().send() {
+            Err(Error::UnconfirmedAll) => {}
+            _ => panic!("Unconfirmed request wasn't refused."),
+        }
+        match all.change_state().brightness(0.1).send() {
+            Err(Error::UnconfirmedAll) => {}
+            _ => panic!("Unconfirmed request wasn't refused."),
+        }
+        match all.set_state().power(true).if_connected().send() {
+            Err(Error::UnconfirmedAll) => {}
+            _ => panic!("Unconfirmed request wasn't refused."),
+        }
+        assert!(all.toggle().request().guarded);
+        assert!(!all.list().guarded);
+        let all = client.select(Selector::All).confirm_all();
+        assert!(!all.toggle().request().guarded);
+        let some = client.select(Selector::Label("Desk".to_string()));
+        assert!(!some.toggle().request().guarded);
+        let client = Client::new("foo");
+        assert!(!client.select(Selector::All).toggle().request().guarded);
+    }
+    #[test]
+    fn guard_all_set_states() {
+        let client = Client::builder("foo")
+            .guard_all(true)
+            .build()
+            .expect("Failed to build client.");
+        let mut states = client.set_states();
+        states.add(
+            Selector::Label("Desk".to_string()),
+            State::new().power(true),
+        );
+        assert!(!states.request().guarded);
+        states.add(Selector::All.zoned(1), State::new().power(false));
+        match states.send() {
+            Err(Error::UnconfirmedAll) => {}
+            _ => panic!("Unconfirmed request wasn't refused."),
+        }
+        match states.send_skip_offline() {
+            Err(Error::UnconfirmedAll) => {}
+            _ => panic!("Unconfirmed request wasn't refused."),
+        }
+        states.confirm_all();
+        assert!(!states.request().guarded);
+        let client = Client::new("foo");
+        let mut states = client.set_states();
+        states.add(Selector::All, State::new().power(false));
+        assert!(!states.request().guarded);
+    }
+    #[test]
+    fn set_zones() {
+        let client = Client::new("foo");
+        let strip = client.select(Selector::Label("Strip".to_string()));
+        let zones = strip.set_zones(vec![Color::Red, Color::Red, Color::Blue]);
+        let payloads = zones.payloads();
+        assert_eq!(payloads.len(), 1);
+        let json = serde_json::to_value(&payloads[0]).expect("Failed to serialize zones.");
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "states": [
+                    { "selector": "label:Strip|0|1", "color": "red" },
+                    { "selector": "label:Strip|2", "color": "blue" },
+                ],
+            })
+        );
+        // Alternating colors can't be merged, so they're split across requests.
+        let gradient = (0..120)
+            .map(|i| if i % 2 == 0 { Color::Red } else { Color::Blue })
+            .collect::<Vec<_>>();
+        let payloads = strip.set_zones(gradient).payloads();
+        assert_eq!(payloads.len(), 3);
+        assert!(payloads.iter().all(|p| p.new.len() <= MAX_STATES));
+        let json = serde_json::to_value(&payloads[2]).expect("Failed to serialize zones.");
+        assert_eq!(json["states"][0]["selector"], "label:Strip|100");
+        let zones = strip.set_zones(vec![Color::Red; 257]);
+        assert_eq!(
+            zones.validate(),
+            Err(ZoneValidationError::TooManyZones(257))
+        );
+        match zones.send() {
+            Err(Error::InvalidZones(ZoneValidationError::TooManyZones(257))) => {}
+            _ => panic!("Too many zones weren't refused."),
+        }
+        assert_eq!(strip.set_zones(vec![Color::Red; 256]).validate(), Ok(()));
+    }
+    #[test]
+    fn set_zones_multizone() {
+        let light = |pid: u32| {
+            serde_json::from_value::<Light>(serde_json::json!({
+                "id": "d3b2f2d97452",
+                "uuid": "8fa5f072-af97-44ed-ae54-e70fd7bd9d20",
+                "label": "Strip",
+                "connected": true,
+                "power": "on",
+                "color": { "hue": 250.0, "saturation": 0.5, "kelvin": 3500 },
+                "brightness": 0.5,
+                "group": { "id": "1c8de82b81f445e7cfaafae49b259c71", "name": "Lounge" },
+                "location": { "id": "1d6fe8ef0fde4c6d77b0012dc736662c", "name": "Home" },
+                "product": { "name": "Product", "product_id": pid }
+            }))
+            .expect("Failed to parse light.")
+        };
+        let strip = light(Product::LIFXZ.pid());
+        let bulb = light(Product::LIFXA19.pid());
+        assert_eq!(check_multizone(&[strip.clone()]), Ok(()));
+        assert_eq!(
+            check_multizone(&[strip, bulb.clone()]),
+            Err(ZoneValidationError::NotMultizone(Product::LIFXA19))
+        );
+        let client = Client::builder("foo")
+            .cache_ttl(StdDuration::from_secs(3600))
+            .build()
+            .expect("Failed to build client.");
+        let selected = client.select(Selector::Label("Strip".to_string()));
+        // Nothing is known about the lights yet.
+        assert_eq!(selected.set_zones(vec![Color::Red]).validate(), Ok(()));
+        if let Some(cache) = client.cache.as_ref() {
+            cache.insert("label:Strip".to_string(), &vec![bulb].into());
+        }
+        assert_eq!(
+            selected.set_zones(vec![Color::Red]).validate(),
+            Err(ZoneValidationError::NotMultizone(Product::LIFXA19))
+        );
     }
 }
@@ -0,0 +1,612 @@
+use std::io::{self, BufReader, Bytes, Read};
+use std::iter::FromIterator;
+use std::ops::Deref;
+
+use serde::{de::Error as DeError, Deserialize, Deserializer};
+
+use crate::common::Product;
+use crate::http::{
+    client::{Client, Error, SetStates},
+    reachability::Reachability,
+    selector::Selector,
+    state::{Color, Power, State},
+};
+
+/// The color of a light, as reported by the API.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub struct LightColor {
+    /// The hue, in degrees (0–360).
+    pub hue: f32,
+    /// The saturation (0–1).
+    pub saturation: f32,
+    /// The color temperature, in Kelvin.
+    pub kelvin: u16,
+}
+
+impl From<LightColor> for Color {
+    /// Converts to an HSBK color (without brightness, which is reported separately), rounding
+    /// the hue to the nearest degree.
+    fn from(color: LightColor) -> Self {
+        Self::Hsbk(
+            Some(color.hue.round() as u16 % 360),
+            Some(color.saturation),
+            None,
+            Some(color.kelvin),
+        )
+    }
+}
+
+/// A group to which a light belongs.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Group {
+    /// The unique ID of the group.
+    pub id: String,
+    /// The human-readable name of the group.
+    pub name: String,
+}
+
+/// A location to which a light belongs.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Location {
+    /// The unique ID of the location.
+    pub id: String,
+    /// The human-readable name of the location.
+    pub name: String,
+}
+
+/// The capabilities of a product, as reported by the API.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(default)]
+pub struct Capabilities {
+    /// Whether the product supports colors (and not only white temperatures).
+    pub has_color: bool,
+    /// Whether the product supports infrared light.
+    pub has_ir: bool,
+    /// Whether the product supports HEV (germicidal "clean") cycles.
+    pub has_hev: bool,
+    /// Whether the product has multiple zones.
+    pub has_multizone: bool,
+}
+
+/// Information about the product a light is, as reported by the API.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct ProductInfo {
+    /// The consumer-friendly name of the product.
+    pub name: String,
+    /// The numeric product ID, if reported.
+    #[serde(default)]
+    pub product_id: Option<u32>,
+    /// The capabilities of the product.
+    #[serde(default)]
+    pub capabilities: Capabilities,
+}
+
+impl ProductInfo {
+    /// The (known) product matching the reported product ID, if any.
+    pub fn product(&self) -> Option<Product> {
+        self.product_id.and_then(Product::from_pid)
+    }
+}
+
+/// A light associated with an account, as returned by
+/// [`Selected::list`](struct.Selected.html#method.list).
+#[derive(Clone, Debug, Deserialize)]
+pub struct Light {
+    /// The serial number of the device.
+    pub id: String,
+    /// The UUID of the device.
+    pub uuid: String,
+    /// The user-assigned label of the device.
+    pub label: String,
+    /// Whether the device is currently reachable by the API.
+    pub connected: bool,
+    /// The current power state of the device.
+    pub power: Power,
+    /// The current color of the device.
+    pub color: LightColor,
+    /// The current brightness level (0–1) of the device.
+    pub brightness: f32,
+    /// The group to which the device belongs.
+    pub group: Group,
+    /// The location to which the device belongs.
+    pub location: Location,
+    /// The product the device is, if reported.
+    #[serde(default)]
+    pub product: Option<ProductInfo>,
+    /// The current maximum infrared level (0–1), for devices which support infrared.
+    #[serde(default, deserialize_with = "lenient_level")]
+    pub infrared: Option<f32>,
+    /// When the device was last seen by the API, as an ISO 8601 timestamp, if reported.
+    #[serde(default)]
+    pub last_seen: Option<String>,
+    /// How long ago (in seconds) the device was last seen by the API, if reported.
+    #[serde(default)]
+    pub seconds_since_seen: Option<f64>,
+}
+
+/// The forms in which the API reports levels: usually numbers, but sometimes strings (the
+/// documented listing reports infrared as `"1.0"`, for instance).
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LevelRepr {
+    Number(f32),
+    Text(String),
+}
+
+fn lenient_level<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f32>, D::Error> {
+    match Option::<LevelRepr>::deserialize(deserializer)? {
+        Some(LevelRepr::Number(level)) => Ok(Some(level)),
+        Some(LevelRepr::Text(text)) => text.trim().parse().map(Some).map_err(DeError::custom),
+        None => Ok(None),
+    }
+}
+
+impl Light {
+    /// Whether the light can currently be reached by the API.
+    ///
+    /// Lists only report whether a light is connected, so this is either `Ok` or `Offline`.
+    pub fn reachability(&self) -> Reachability {
+        if self.connected {
+            Reachability::Ok
+        } else {
+            Reachability::Offline
+        }
+    }
+    /// The light's current color and brightness, as an HSBK color.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::new("foo");
+    /// let lights = client.select(Selector::Label("Desk".to_string())).lights()?;
+    /// if let Some(desk) = lights.first() {
+    ///     println!("{} is {}.", desk.label, desk.current_color());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn current_color(&self) -> Color {
+        match Color::from(self.color) {
+            Color::Hsbk(h, s, _, k) => Color::Hsbk(h, s, Some(self.brightness), k),
+            color => color,
+        }
+    }
+    /// Whether the light supports HEV (germicidal "clean") cycles.
+    ///
+    /// Known products are checked with [`Product::hev`](../common/enum.Product.html#method.hev);
+    /// otherwise, the capabilities reported by the API are used.
+    pub fn supports_hev(&self) -> bool {
+        match &self.product {
+            Some(info) => match info.product() {
+                Some(product) => product.hev(),
+                None => info.capabilities.has_hev,
+            },
+            None => false,
+        }
+    }
+    /// Whether the given selector (locally) matches this light.
+    ///
+    /// Labels and names are compared exactly; scene selectors never match, since scene membership
+    /// isn't reported with the light.
+    pub(crate) fn matched_by(&self, selector: &Selector) -> bool {
+        match selector {
+            Selector::All => true,
+            Selector::Label(label) => &self.label == label,
+            Selector::Id(id) => &self.id == id,
+            Selector::GroupId(id) => &self.group.id == id,
+            Selector::Group(name) => &self.group.name == name,
+            Selector::LocationId(id) => &self.location.id == id,
+            Selector::Location(name) => &self.location.name == name,
+            Selector::SceneId(_) => false,
+        }
+    }
+}
+
+/// A collection of lights, as returned by [`Selected::lights`](struct.Selected.html#method.lights).
+///
+/// Dereferences to a slice of [`Light`](struct.Light.html)s, and offers a few client-side queries
+/// which the API doesn't support directly.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(transparent)]
+pub struct Lights {
+    lights: Vec<Light>,
+}
+
+impl Lights {
+    /// Retains only the lights whose labels contain the given text, ignoring case.
+    ///
+    /// This filtering is performed locally; the API has no substring matching.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::new("foo");
+    /// let lamps = client
+    ///     .select(Selector::All)
+    ///     .lights()?
+    ///     .filter_label_contains("lamp");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn filter_label_contains(self, text: &str) -> Self {
+        let text = text.to_lowercase();
+        self.into_iter()
+            .filter(|light| light.label.to_lowercase().contains(&text))
+            .collect()
+    }
+    /// Builds a batched request setting each light (by ID) to the state computed for it.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::new("foo");
+    /// // Dim every light which is currently on to 20%.
+    /// let on = client
+    ///     .select(Selector::All)
+    ///     .lights()?
+    ///     .into_iter()
+    ///     .filter(|light| light.power == true.into())
+    ///     .collect::<Lights>();
+    /// on.into_set_states(&client, |_| State::builder().brightness(0.2))
+    ///     .send()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn into_set_states<'a, F>(self, client: &'a Client, f: F) -> SetStates<'a>
+    where
+        F: Fn(&Light) -> State,
+    {
+        let mut states = client.set_states();
+        for light in &self {
+            states.add(Selector::Id(light.id.clone()), f(light));
+        }
+        states
+    }
+    /// Unwraps the underlying list of lights.
+    pub fn into_vec(self) -> Vec<Light> {
+        self.lights
+    }
+}
+
+impl Deref for Lights {
+    type Target = [Light];
+    fn deref(&self) -> &Self::Target {
+        &self.lights
+    }
+}
+
+impl From<Vec<Light>> for Lights {
+    fn from(lights: Vec<Light>) -> Self {
+        Self { lights }
+    }
+}
+
+impl FromIterator<Light> for Lights {
+    fn from_iter<I: IntoIterator<Item = Light>>(iter: I) -> Self {
+        Self {
+            lights: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl IntoIterator for Lights {
+    type Item = Light;
+    type IntoIter = ::std::vec::IntoIter<Light>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.lights.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Lights {
+    type Item = &'a Light;
+    type IntoIter = ::std::slice::Iter<'a, Light>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.lights.iter()
+    }
+}
+
+/// Lazily deserializes a JSON array of lights from a byte stream.
+///
+/// Only one light is held in memory at a time; the underlying reader is consumed as iteration
+/// proceeds.
+pub(crate) struct LightStream<R: Read> {
+    bytes: Option<Bytes<BufReader<R>>>,
+    pending: Option<Error>,
+    started: bool,
+    done: bool,
+}
+
+fn invalid(message: &str) -> serde_json::Error {
+    serde_json::Error::io(io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+impl<R: Read> LightStream<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self {
+            bytes: Some(BufReader::new(reader).bytes()),
+            pending: None,
+            started: false,
+            done: false,
+        }
+    }
+    /// Creates a stream which yields the given error and then ends.
+    pub(crate) fn failed(err: Error) -> Self {
+        Self {
+            bytes: None,
+            pending: Some(err),
+            started: false,
+            done: false,
+        }
+    }
+    fn byte(&mut self) -> Result<Option<u8>, serde_json::Error> {
+        match self.bytes.as_mut() {
+            Some(bytes) => bytes.next().transpose().map_err(serde_json::Error::io),
+            None => Ok(None),
+        }
+    }
+    fn significant(&mut self) -> Result<Option<u8>, serde_json::Error> {
+        loop {
+            match self.byte()? {
+                Some(b' ') | Some(b'\n') | Some(b'\r') | Some(b'\t') => {}
+                b => return Ok(b),
+            }
+        }
+    }
+    fn advance(&mut self) -> Result<Option<Light>, serde_json::Error> {
+        let mut byte = self.significant()?;
+        if self.started {
+            match byte {
+                Some(b',') => byte = self.significant()?,
+                Some(b']') => return Ok(None),
+                _ => return Err(invalid("Expected `,` or `]` after light.")),
+            }
+        } else {
+            self.started = true;
+            if byte != Some(b'[') {
+                return Err(invalid("Expected `[` at start of light list."));
+            }
+            byte = self.significant()?;
+            if byte == Some(b']') {
+                return Ok(None);
+            }
+        }
+        if byte != Some(b'{') {
+            return Err(invalid("Expected `{` at start of light."));
+        }
+        let mut buf = vec![b'{'];
+        let mut depth = 1;
+        let mut in_string = false;
+        let mut escaped = false;
+        while depth > 0 {
+            let b = self
+                .byte()?
+                .ok_or_else(|| invalid("Unexpected end of light list."))?;
+            buf.push(b);
+            if in_string {
+                if escaped {
+                    escaped = false;
+                } else if b == b'\\' {
+                    escaped = true;
+                } else if b == b'"' {
+                    in_string = false;
+                }
+            } else {
+                match b {
+                    b'"' => in_string = true,
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => depth -= 1,
+                    _ => {}
+                }
+            }
+        }
+        serde_json::from_slice(&buf).map(Some)
+    }
+}
+
+impl<R: Read> Iterator for LightStream<R> {
+    type Item = Result<Light, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(err) = self.pending.take() {
+            self.done = true;
+            return Some(Err(err));
+        }
+        if self.done {
+            return None;
+        }
+        match self.advance() {
+            Ok(Some(light)) => Some(Ok(light)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(Error::Deserialization(e)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const LIGHT: &str = r#"{
+        "id": "d3b2f2d97452",
+        "uuid": "8fa5f072-af97-44ed-ae54-e70fd7bd9d20",
+        "label": "Left {Lamp}",
+        "connected": true,
+        "power": "on",
+        "color": { "hue": 250.0, "saturation": 0.5, "kelvin": 3500 },
+        "brightness": 0.5,
+        "group": { "id": "1c8de82b81f445e7cfaafae49b259c71", "name": "Lounge" },
+        "location": { "id": "1d6fe8ef0fde4c6d77b0012dc736662c", "name": "Home" }
+    }"#;
+    #[test]
+    fn documented() {
+        // The example response from the API documentation.
+        let body = r#"[
+            {
+                "id": "d3b2f2d97452",
+                "uuid": "8fa5f072-af97-44ed-ae54-e70fd7bd9d20",
+                "label": "Left Lamp",
+                "connected": true,
+                "power": "on",
+                "color": { "hue": 250.0, "saturation": 0.5, "kelvin": 3500 },
+                "infrared": "1.0",
+                "brightness": 0.5,
+                "group": { "id": "1c8de82b81f445e7cfaafae49b259c71", "name": "Lounge" },
+                "location": { "id": "1d6fe8ef0fde4c6d77b0012dc736662c", "name": "Home" },
+                "last_seen": "2015-03-02T08:53:02.867+00:00",
+                "seconds_since_seen": 0.002869418,
+                "product": {
+                    "name": "LIFX+ A19",
+                    "identifier": "lifx_plus_a19",
+                    "company": "LIFX",
+                    "capabilities": {
+                        "has_color": true,
+                        "has_variable_color_temp": true,
+                        "has_ir": true,
+                        "has_multizone": false
+                    }
+                }
+            }
+        ]"#;
+        let lights: Lights = serde_json::from_str(body).expect("Failed to parse lights.");
+        let light = &lights[0];
+        assert_eq!(light.id, "d3b2f2d97452");
+        assert_eq!(light.power, Power::from(true));
+        assert_eq!(light.reachability(), Reachability::Ok);
+        assert_eq!(light.infrared, Some(1.0));
+        assert_eq!(
+            light.last_seen.as_ref().map(String::as_str),
+            Some("2015-03-02T08:53:02.867+00:00")
+        );
+        assert_eq!(light.seconds_since_seen, Some(0.002_869_418));
+        let product = light.product.as_ref().expect("Product missing.");
+        assert_eq!(product.name, "LIFX+ A19");
+        assert!(product.capabilities.has_ir);
+        let light: Light = serde_json::from_str(LIGHT).expect("Failed to parse light.");
+        assert_eq!(light.infrared, None);
+        assert_eq!(light.last_seen, None);
+        assert_eq!(light.seconds_since_seen, None);
+        let json = LIGHT.replace(r#""connected""#, r#""infrared": 0.25, "connected""#);
+        let light: Light = serde_json::from_str(&json).expect("Failed to parse light.");
+        assert_eq!(light.infrared, Some(0.25));
+    }
+    #[test]
+    fn current_color() {
+        let light: Light = serde_json::from_str(LIGHT).expect("Failed to parse light.");
+        assert_eq!(
+            Color::from(light.color),
+            Color::Hsbk(Some(250), Some(0.5), None, Some(3500))
+        );
+        assert_eq!(
+            light.current_color(),
+            Color::Hsbk(Some(250), Some(0.5), Some(0.5), Some(3500))
+        );
+        let json = LIGHT.replace("250.0", "359.7");
+        let light: Light = serde_json::from_str(&json).expect("Failed to parse light.");
+        assert_eq!(
+            light.current_color(),
+            Color::Hsbk(Some(0), Some(0.5), Some(0.5), Some(3500))
+        );
+    }
+    #[test]
+    fn stream() {
+        let body = format!("[{}, {}]", LIGHT, LIGHT.replace("true", "false"));
+        let lights = LightStream::new(body.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .expect("Failed to parse lights.");
+        assert_eq!(lights.len(), 2);
+        assert_eq!(lights[0].label, "Left {Lamp}");
+        assert_eq!(lights[0].color.kelvin, 3500);
+        assert!(lights[0].connected);
+        assert!(!lights[1].connected);
+        assert_eq!(lights[1].group.name, "Lounge");
+    }
+    #[test]
+    fn supports_hev() {
+        let light: Light = serde_json::from_str(LIGHT).expect("Failed to parse light.");
+        assert!(light.product.is_none());
+        assert!(!light.supports_hev());
+        let product = r#""product": { "name": "LIFX Clean", "product_id": 90 },"#;
+        let json = LIGHT.replace(r#""connected""#, &format!(r#"{} "connected""#, product));
+        let light: Light = serde_json::from_str(&json).expect("Failed to parse light.");
+        assert_eq!(
+            light.product.as_ref().and_then(ProductInfo::product),
+            Some(Product::LIFXClean)
+        );
+        assert!(light.supports_hev());
+        let product = r#""product": { "name": "Future", "capabilities": { "has_hev": true } },"#;
+        let json = LIGHT.replace(r#""connected""#, &format!(r#"{} "connected""#, product));
+        let light: Light = serde_json::from_str(&json).expect("Failed to parse light.");
+        assert!(light.supports_hev());
+    }
+    #[test]
+    fn matched_by() {
+        let light: Light = serde_json::from_str(LIGHT).expect("Failed to parse light.");
+        assert!(light.matched_by(&Selector::All));
+        assert!(light.matched_by(&Selector::Label("Left {Lamp}".to_string())));
+        assert!(!light.matched_by(&Selector::Label("Right {Lamp}".to_string())));
+        assert!(light.matched_by(&Selector::Id("d3b2f2d97452".to_string())));
+        assert!(light.matched_by(&Selector::Group("Lounge".to_string())));
+        assert!(light.matched_by(&Selector::LocationId(
+            "1d6fe8ef0fde4c6d77b0012dc736662c".to_string()
+        )));
+        assert!(!light.matched_by(&Selector::SceneId("abcd".to_string())));
+    }
+    #[test]
+    fn filter_label_contains() {
+        let body = format!(
+            "[{}, {}, {}]",
+            LIGHT,
+            LIGHT.replace("Left {Lamp}", "Ceiling"),
+            LIGHT.replace("Left {Lamp}", "Floor LAMP")
+        );
+        let lights: Lights = serde_json::from_str(&body).expect("Failed to parse lights.");
+        assert_eq!(lights.len(), 3);
+        let lamps = lights.filter_label_contains("Lamp");
+        let labels = lamps.iter().map(|l| l.label.as_str()).collect::<Vec<_>>();
+        assert_eq!(labels, vec!["Left {Lamp}", "Floor LAMP"]);
+    }
+    #[test]
+    fn into_set_states() {
+        use crate::http::AsRequest;
+        let body = format!(
+            "[{}, {}]",
+            LIGHT,
+            LIGHT.replace("d3b2f2d97452", "d073d5000001")
+        );
+        let lights: Lights = serde_json::from_str(&body).expect("Failed to parse lights.");
+        let client = Client::new("foo");
+        let states = lights.into_set_states(&client, |light| {
+            State::builder().brightness(light.brightness / 2.0)
+        });
+        let json = serde_json::to_value(states.body()).expect("Failed to serialize states.");
+        assert_eq!(
+            json["states"],
+            serde_json::json!([
+                { "selector": "id:d3b2f2d97452", "brightness": 0.25 },
+                { "selector": "id:d073d5000001", "brightness": 0.25 },
+            ])
+        );
+    }
+    #[test]
+    fn empty() {
+        let mut stream = LightStream::new(&b" [ ] "[..]);
+        assert!(stream.next().is_none());
+    }
+    #[test]
+    fn malformed() {
+        let mut stream = LightStream::new(&b"{}"[..]);
+        assert!(stream.next().expect("Stream ended early.").is_err());
+        assert!(stream.next().is_none());
+        let body = format!("[{}", LIGHT);
+        let mut stream = LightStream::new(body.as_bytes());
+        assert!(stream.next().expect("Stream ended early.").is_ok());
+        assert!(stream.next().expect("Stream ended early.").is_err());
+    }
+}
@@ -1,9 +1,16 @@
 //! Control LIFX lights over the internet.
 
+mod light;
+pub use self::light::*;
+mod reachability;
+pub use self::reachability::*;
 mod selector;
 pub use self::selector::*;
 mod state;
 pub use self::state::Error as ColorValidationError;
-pub use self::state::{Color, ColorParseError, State, StateChange};
+pub use self::state::{
+    Color, ColorBuilder, ColorParseError, Duration, HsbkBuilder, Kelvin, Level, NamedColor, Power,
+    State, StateChange,
+};
 mod client;
 pub use self::client::*;
@@ -1,9 +1,94 @@
+use crate::http::client::{parse_json, Error};
+
 /// Represents the reachability status of a device.
+///
+/// Deserializes from the `status` reported for each light in the results of a state change (see
+/// [`OperationResult`](struct.OperationResult.html)).
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
 pub enum Reachability {
-	/// The light is reachable and has received the request.
-	Ok,
-	/// The light did not acknowledge the request.
-	TimedOut,
-	/// The light is currently offline (physically powered off or unreachable over the network).
-	Offline,
+    /// The light is reachable and has received the request.
+    Ok,
+    /// The light did not acknowledge the request.
+    TimedOut,
+    /// The light is currently offline (physically powered off or unreachable over the network).
+    Offline,
+}
+
+/// The status of a light after an operation, which shares the semantics of
+/// [`Reachability`](enum.Reachability.html).
+pub type OperationStatus = Reachability;
+
+/// The outcome of an operation (e.g., a state change, toggle, or effect) for a single light.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OperationResult {
+    /// The serial number of the light.
+    pub id: String,
+    /// The user-assigned label of the light.
+    pub label: String,
+    /// Whether the light received the request.
+    pub status: OperationStatus,
+}
+
+/// The body of an operation's response.
+#[derive(Deserialize)]
+struct OperationResults {
+    results: Vec<OperationResult>,
+}
+
+impl OperationResult {
+    /// Reads the per-light results from the response to a state-changing request.
+    ///
+    /// The API reports success (HTTP 207) even when some lights didn't receive the request, so
+    /// this is the way to find out which ones did. Requests which turned out to be unnecessary
+    /// (and so weren't sent) have no results.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::{prelude::*, OperationResult};
+    /// # fn run() -> Result<(), lifxi::http::Error> {
+    /// let client = Client::new("foo");
+    /// let response = client.select(Selector::All).toggle().send()?;
+    /// for result in OperationResult::from_response(response)? {
+    ///     if result.status != Reachability::Ok {
+    ///         println!("{} didn't toggle ({:?}).", result.label, result.status);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_response(response: reqwest::Response) -> Result<Vec<Self>, Error> {
+        if response.status() == reqwest::StatusCode::NO_CONTENT {
+            return Ok(Vec::new());
+        }
+        parse_json::<OperationResults>(response).map(|body| body.results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn results() {
+        let body = r#"{
+            "results": [
+                { "id": "d3b2f2d97452", "label": "Left Lamp", "status": "ok" },
+                { "id": "d073d5000001", "label": "Right Lamp", "status": "timed_out" },
+                { "id": "d073d5000002", "label": "Porch", "status": "offline" }
+            ]
+        }"#;
+        let body: OperationResults = serde_json::from_str(body).expect("Failed to parse results.");
+        let statuses = body.results.iter().map(|r| r.status).collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            vec![
+                Reachability::Ok,
+                Reachability::TimedOut,
+                Reachability::Offline
+            ]
+        );
+        assert_eq!(body.results[1].label, "Right Lamp");
+        let unknown = r#"{ "id": "a", "label": "b", "status": "melted" }"#;
+        assert!(serde_json::from_str::<OperationResult>(unknown).is_err());
+    }
 }
@@ -50,7 +50,20 @@ impl Serialize for Selectors {
     }
 }
 
-impl Select for Selectors {}
+impl Select for Selectors {
+    fn targets_all(&self) -> bool {
+        self.selectors
+            .iter()
+            .any(|s| s == "all" || s.starts_with("all|"))
+    }
+    fn parts(&self) -> Vec<Zoned> {
+        self.selectors
+            .iter()
+            .map(|s| s.parse())
+            .collect::<Result<_, _>>()
+            .unwrap_or_default()
+    }
+}
 
 /// Selectors are used to identify one or more lights belonging to a particular account.
 ///
@@ -66,7 +79,7 @@ impl Select for Selectors {}
 ///
 /// A random device can be chosen from the list of devices matching a selector via
 /// [the `Randomize` trait](trait.Randomize.html).
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub enum Selector {
     /// All devices on the user's account.
     All,
@@ -145,6 +158,54 @@ pub enum SelectorParseError {
     /// assert_eq!(selector, Err(SelectorParseError::UnknownLabel));
     /// ```
     UnknownLabel,
+    /// A zone constraint could not be parsed as an integer between 0 and 255.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::{prelude::*, Zoned};
+    /// let selector = "all|256".parse::<Zoned>();
+    /// assert_eq!(selector, Err(SelectorParseError::InvalidZone));
+    /// ```
+    InvalidZone,
+    /// A randomized selector was expected, but the `:random` suffix was missing.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::{prelude::*, Random};
+    /// let selector = "all".parse::<Random<Selector>>();
+    /// assert_eq!(selector, Err(SelectorParseError::NotRandom));
+    /// ```
+    NotRandom,
+    /// An ID selector's value wasn't a plausible serial number (a string of hex digits).
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// let selector = Selector::Id("Desk Lamp".to_string());
+    /// assert_eq!(selector.validate(), Err(SelectorParseError::InvalidSerial));
+    /// ```
+    InvalidSerial,
+    /// A selector value contained a character with special meaning in selectors (`,`, `|`, or
+    /// `:`).
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// let selector = Selector::from_parts("label", "Desk,Lamp");
+    /// assert_eq!(selector, Err(SelectorParseError::ReservedCharacter));
+    /// ```
+    ReservedCharacter,
+    /// A scene selector was expected, but another kind of selector was given.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// let client = Client::new("foo");
+    /// let scenes = client.scenes();
+    /// let activate = scenes.activate_selector(Selector::All);
+    /// assert_eq!(activate.err(), Some(SelectorParseError::NotScene));
+    /// ```
+    NotScene,
 }
 
 impl fmt::Display for SelectorParseError {
@@ -153,9 +214,14 @@ impl fmt::Display for SelectorParseError {
             f,
             "{}",
             match self {
-                SelectorParseError::NoLabel => "Unrecognized selector.",
-                SelectorParseError::NoValue => "No value given for label.",
-                SelectorParseError::UnknownLabel => "Unrecognized label.",
+                Self::NoLabel => "Unrecognized selector.",
+                Self::NoValue => "No value given for label.",
+                Self::UnknownLabel => "Unrecognized label.",
+                Self::InvalidZone => "Invalid zone.",
+                Self::NotRandom => "Expected :random suffix.",
+                Self::InvalidSerial => "Invalid serial number.",
+                Self::ReservedCharacter => "Reserved character in selector value.",
+                Self::NotScene => "Expected a scene selector.",
             }
         )
     }
@@ -178,16 +244,7 @@ impl FromStr for Selector {
                         if value.trim().is_empty() {
                             return Err(NoValue);
                         }
-                        match label {
-                            "label" => Ok(Label(value)),
-                            "id" => Ok(Id(value)),
-                            "group_id" => Ok(GroupId(value)),
-                            "group" => Ok(Group(value)),
-                            "location_id" => Ok(LocationId(value)),
-                            "location" => Ok(Location(value)),
-                            "scene_id" => Ok(SceneId(value)),
-                            _ => Err(UnknownLabel),
-                        }
+                        Self::with_kind(label, value)
                     } else {
                         Err(NoValue)
                     }
@@ -218,11 +275,31 @@ impl Serialize for Selector {
 /// ## Randomization
 /// Like root-level selectors, zoned selectors may be randomized using
 /// [the `Randomize` trait](trait.Randomize.html).
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Zoned {
     selector: Selector,
     zoning: Zones,
 }
 
+impl Zoned {
+    /// The selector being constrained.
+    pub fn selector(&self) -> &Selector {
+        &self.selector
+    }
+    /// The zones to which the selector is constrained.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// let selector = Selector::Label("Strip".to_string()).zoned(2..5);
+    /// assert_eq!(selector.zones().len(), 3);
+    /// assert!(selector.zones().contains(4));
+    /// ```
+    pub fn zones(&self) -> &Zones {
+        &self.zoning
+    }
+}
+
 impl fmt::Display for Zoned {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.selector)?;
@@ -233,18 +310,78 @@ impl fmt::Display for Zoned {
     }
 }
 
+impl FromStr for Zoned {
+    type Err = self::SelectorParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split('|');
+        let selector = parts.next().unwrap_or_default().parse::<Selector>()?;
+        let zoning = parts
+            .map(|z| z.trim().parse::<u8>())
+            .collect::<Result<Zones, _>>()
+            .map_err(|_| SelectorParseError::InvalidZone)?;
+        Ok(Self { selector, zoning })
+    }
+}
+
+impl<'de> Deserialize<'de> for Zoned {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse::<Self>().map_err(DeError::custom)
+    }
+}
+
 impl Serialize for Zoned {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         serializer.serialize_str(&format!("{}", self))
     }
 }
 
-#[doc(hidden)]
-/// Represents a set of zones. Used to constrain selectors further. Not for direct use.
+/// Represents a set of zones. Used to constrain selectors further.
+///
+/// Usually constructed implicitly from a zone, a range of zones, or a list of zones passed to
+/// [`Selector::zoned`](enum.Selector.html#method.zoned).
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Zones {
     list: Vec<u8>,
 }
 
+impl Zones {
+    /// The number of zones in the set.
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+    /// Whether the set contains no zones.
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+    /// Whether the set contains the given zone.
+    pub fn contains(&self, zone: u8) -> bool {
+        self.list.contains(&zone)
+    }
+    /// Iterates over the zones in the set, in order of insertion.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::Zones;
+    /// let zones = Zones::from(vec![3, 1]);
+    /// assert_eq!(zones.iter().collect::<Vec<_>>(), vec![&3, &1]);
+    /// for zone in &zones {
+    ///     assert!(zones.contains(*zone));
+    /// }
+    /// ```
+    pub fn iter(&self) -> ::std::slice::Iter<'_, u8> {
+        self.list.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Zones {
+    type Item = &'a u8;
+    type IntoIter = ::std::slice::Iter<'a, u8>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.list.iter()
+    }
+}
+
 impl From<Vec<u8>> for Zones {
     fn from(list: Vec<u8>) -> Self {
         Self { list }
@@ -307,6 +444,7 @@ impl From<u8> for Zones {
 /// A selector that randomly chooses a device from the resultant list.
 ///
 /// Created by [`Randomize::random`](trait.Randomize.html#method.random).
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Random<T: PureSelect>(T);
 
 impl<T: PureSelect> fmt::Display for Random<T> {
@@ -315,6 +453,29 @@ impl<T: PureSelect> fmt::Display for Random<T> {
     }
 }
 
+impl<T> FromStr for Random<T>
+where
+    T: PureSelect + FromStr<Err = SelectorParseError>,
+{
+    type Err = self::SelectorParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_suffix(":random") {
+            Some(inner) => inner.parse::<T>().map(Random),
+            None => Err(SelectorParseError::NotRandom),
+        }
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Random<T>
+where
+    T: PureSelect + FromStr<Err = SelectorParseError>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse::<Self>().map_err(DeError::custom)
+    }
+}
+
 impl<T: PureSelect> Serialize for Random<T> {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         serializer.serialize_str(&format!("{}", self))
@@ -322,6 +483,125 @@ impl<T: PureSelect> Serialize for Random<T> {
 }
 
 impl Selector {
+    /// Builds a selector of the given kind (e.g., `"group"`) from its value.
+    fn with_kind(kind: &str, value: String) -> Result<Self, SelectorParseError> {
+        use self::Selector::*;
+        match kind {
+            "label" => Ok(Label(value)),
+            "id" => Ok(Id(value)),
+            "group_id" => Ok(GroupId(value)),
+            "group" => Ok(Group(value)),
+            "location_id" => Ok(LocationId(value)),
+            "location" => Ok(Location(value)),
+            "scene_id" => Ok(SceneId(value)),
+            _ => Err(SelectorParseError::UnknownLabel),
+        }
+    }
+    /// Builds a selector from its kind (e.g., `"group"`) and value (e.g., `"Kitchen"`).
+    ///
+    /// This is the inverse of [`kind`](#method.kind) and [`value`](#method.value), and is meant
+    /// for situations (like user interfaces) where the two are already separate. The value is
+    /// trimmed, and may not contain characters with special meaning in selectors (`,`, `|`, or
+    /// `:`). For the `"all"` kind, the value is ignored.
+    ///
+    /// ## Examples
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// let selector = Selector::from_parts("group", "Kitchen");
+    /// assert_eq!(selector, Ok(Selector::Group("Kitchen".to_string())));
+    /// assert_eq!(Selector::from_parts("all", ""), Ok(Selector::All));
+    /// assert_eq!(
+    ///     Selector::from_parts("room", "Kitchen"),
+    ///     Err(SelectorParseError::UnknownLabel)
+    /// );
+    /// assert_eq!(Selector::from_parts("label", " "), Err(SelectorParseError::NoValue));
+    /// ```
+    pub fn from_parts(kind: &str, value: &str) -> Result<Self, SelectorParseError> {
+        if kind == "all" {
+            return Ok(Self::All);
+        }
+        let value = value.trim();
+        if value.is_empty() {
+            Err(SelectorParseError::NoValue)
+        } else if value.contains(&[',', '|', ':'][..]) {
+            Err(SelectorParseError::ReservedCharacter)
+        } else {
+            Self::with_kind(kind, value.to_string())
+        }
+    }
+    /// The kind of the selector, as used in its string form (e.g., `"group"` or `"all"`).
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// assert_eq!(Selector::LocationId("abcd".to_string()).kind(), "location_id");
+    /// ```
+    pub fn kind(&self) -> &'static str {
+        use self::Selector::*;
+        match self {
+            All => "all",
+            Label(_) => "label",
+            Id(_) => "id",
+            GroupId(_) => "group_id",
+            Group(_) => "group",
+            LocationId(_) => "location_id",
+            Location(_) => "location",
+            SceneId(_) => "scene_id",
+        }
+    }
+    /// The value of the selector, if it has one (only `Selector::All` doesn't).
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// assert_eq!(Selector::Label("Desk".to_string()).value(), Some("Desk"));
+    /// assert_eq!(Selector::All.value(), None);
+    /// ```
+    pub fn value(&self) -> Option<&str> {
+        use self::Selector::*;
+        match self {
+            All => None,
+            Label(value) | Id(value) | GroupId(value) | Group(value) | LocationId(value)
+            | Location(value) | SceneId(value) => Some(value),
+        }
+    }
+    /// Checks the selector for obvious mistakes before it's sent.
+    ///
+    /// Values may not be empty, and IDs must look like serial numbers (e.g., `d073d5000001`);
+    /// only the characters are checked, not the length, so that other serial formats still pass.
+    /// This catches, for example, labels mistakenly passed as IDs.
+    ///
+    /// ## Examples
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// assert!(Selector::Id("d073d5000001".to_string()).validate().is_ok());
+    /// assert_eq!(
+    ///     Selector::Id("d073d5 000001".to_string()).validate(),
+    ///     Err(SelectorParseError::InvalidSerial)
+    /// );
+    /// assert_eq!(
+    ///     Selector::Label(String::new()).validate(),
+    ///     Err(SelectorParseError::NoValue)
+    /// );
+    /// ```
+    pub fn validate(&self) -> Result<(), SelectorParseError> {
+        use self::Selector::*;
+        match self {
+            All => Ok(()),
+            Id(id) if id.is_empty() => Err(SelectorParseError::NoValue),
+            Id(id) if !id.chars().all(|c| c.is_ascii_hexdigit()) => {
+                Err(SelectorParseError::InvalidSerial)
+            }
+            Label(value) | Id(value) | GroupId(value) | Group(value) | LocationId(value)
+            | Location(value) | SceneId(value) => {
+                if value.trim().is_empty() {
+                    Err(SelectorParseError::NoValue)
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
     /// Constrains the selector to only match the given zone(s).
     /// ## Examples
     /// ```
@@ -359,10 +639,51 @@ impl Selector {
 }
 
 /// Marker trait indicating the potential for use in identifying devices.
-pub trait Select: fmt::Display + Serialize {}
-impl Select for Selector {}
-impl Select for Zoned {}
-impl<T: PureSelect> Select for Random<T> {}
+pub trait Select: fmt::Display + Serialize {
+    /// Whether the selector matches every device on the account.
+    #[doc(hidden)]
+    fn targets_all(&self) -> bool {
+        false
+    }
+    /// The (possibly unconstrained) zoned selectors which together make up this selector, or
+    /// nothing if it can't be broken down.
+    #[doc(hidden)]
+    fn parts(&self) -> Vec<Zoned> {
+        Vec::new()
+    }
+    /// Whether a single device is chosen at random from those matched.
+    #[doc(hidden)]
+    fn is_random(&self) -> bool {
+        false
+    }
+}
+impl Select for Selector {
+    fn targets_all(&self) -> bool {
+        *self == Self::All
+    }
+    fn parts(&self) -> Vec<Zoned> {
+        vec![Zoned {
+            selector: self.clone(),
+            zoning: Zones { list: Vec::new() },
+        }]
+    }
+}
+impl Select for Zoned {
+    fn targets_all(&self) -> bool {
+        self.selector.targets_all()
+    }
+    fn parts(&self) -> Vec<Zoned> {
+        vec![self.clone()]
+    }
+}
+impl<T: PureSelect> Select for Random<T> {
+    fn parts(&self) -> Vec<Zoned> {
+        self.0.parts()
+    }
+    fn is_random(&self) -> bool {
+        true
+    }
+}
 
 /// Marker trait for non-randomized selectors.
 #[doc(hidden)]
@@ -478,5 +799,121 @@ mod tests {
         assert_eq!(selector, Ok(Selector::Location("Summer Home".to_string())));
         let selector = "scene_id:mnop".parse();
         assert_eq!(selector, Ok(Selector::SceneId("mnop".to_string())));
+        let selector = "all|1|2".parse();
+        assert_eq!(selector, Ok(Selector::All.zoned(vec![1, 2])));
+        let selector = "label:Strip|0".parse();
+        assert_eq!(selector, Ok(Selector::Label("Strip".to_string()).zoned(0)));
+        let selector = "all|x".parse::<Zoned>();
+        assert_eq!(selector, Err(SelectorParseError::InvalidZone));
+        let selector = "group:Lounge:random".parse();
+        assert_eq!(selector, Ok(Selector::Group("Lounge".to_string()).random()));
+        let selector = "all|3:random".parse();
+        assert_eq!(selector, Ok(Selector::All.zoned(3).random()));
+    }
+    #[test]
+    fn targets_all() {
+        assert!(Selector::All.targets_all());
+        assert!(Selector::All.zoned(0).targets_all());
+        assert!(!Selector::All.random().targets_all());
+        assert!(!Selector::Group("Lounge".to_string()).targets_all());
+        let combined = Selector::Group("Lounge".to_string()).combine(Selector::All);
+        assert!(combined.targets_all());
+        let combined = Selector::Group("all".to_string()).combine(Selector::Label("x".to_string()));
+        assert!(!combined.targets_all());
+    }
+    #[test]
+    fn parts() {
+        let desk = Selector::Label("Desk".to_string());
+        let parts = desk.parts();
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].selector(), &desk);
+        assert!(parts[0].zones().is_empty());
+        let strip = Selector::Label("Strip".to_string()).zoned(1);
+        let combined = strip.clone().combine(desk.clone().zoned(vec![2, 3]));
+        assert_eq!(
+            combined.parts(),
+            vec![strip.clone(), desk.clone().zoned(vec![2, 3])]
+        );
+        assert!(!combined.is_random());
+        assert_eq!(strip.clone().random().parts(), vec![strip.clone()]);
+        assert!(strip.random().is_random());
+    }
+    #[test]
+    fn combine() {
+        let office = Selector::Label("Office".to_string());
+        let basement = Selector::Label("Basement".to_string());
+        let combined = office.clone().combine(basement.clone());
+        assert_eq!(&format!("{}", combined), "label:Office,label:Basement");
+        let combined = office.clone().zoned(1..3).combine(basement.clone());
+        assert_eq!(&format!("{}", combined), "label:Office|1|2,label:Basement");
+        let combined = office.clone().combine(basement.clone().zoned(4));
+        assert_eq!(&format!("{}", combined), "label:Office,label:Basement|4");
+        let combined = office
+            .zoned(0)
+            .combine(basement.zoned(5))
+            .combine(Selector::Group("Lounge".to_string()));
+        assert_eq!(
+            &format!("{}", combined),
+            "label:Office|0,label:Basement|5,group:Lounge"
+        );
+        let json = serde_json::to_value(&combined).expect("Failed to serialize selectors.");
+        assert_eq!(json, "label:Office|0,label:Basement|5,group:Lounge");
+    }
+    #[test]
+    fn from_parts() {
+        let selectors = vec![
+            Selector::All,
+            Selector::Label("Living Room".to_string()),
+            Selector::Id("d073d5000001".to_string()),
+            Selector::GroupId("efgh".to_string()),
+            Selector::Group("Lounge".to_string()),
+            Selector::LocationId("ijkl".to_string()),
+            Selector::Location("Summer Home".to_string()),
+            Selector::SceneId("mnop".to_string()),
+        ];
+        for selector in selectors {
+            let value = selector.value().unwrap_or_default();
+            let rebuilt = Selector::from_parts(selector.kind(), value);
+            assert_eq!(rebuilt.as_ref(), Ok(&selector));
+            let displayed = format!("{}", selector);
+            assert_eq!(displayed.parse::<Selector>(), Ok(selector));
+        }
+        let reserved = |value: &str| Selector::from_parts("label", value);
+        assert_eq!(reserved("a|1"), Err(SelectorParseError::ReservedCharacter));
+        assert_eq!(
+            reserved("a:random"),
+            Err(SelectorParseError::ReservedCharacter)
+        );
+        assert_eq!(reserved("a,b"), Err(SelectorParseError::ReservedCharacter));
+        assert_eq!(reserved("  Desk "), Ok(Selector::Label("Desk".to_string())));
+    }
+    #[test]
+    fn validate() {
+        assert_eq!(Selector::All.validate(), Ok(()));
+        assert_eq!(Selector::Id("D073D5ABCDEF".to_string()).validate(), Ok(()));
+        let id = |s: &str| Selector::Id(s.to_string()).validate();
+        assert_eq!(id(""), Err(SelectorParseError::NoValue));
+        assert_eq!(id("Kitchen"), Err(SelectorParseError::InvalidSerial));
+        assert_eq!(id("d073d5 00"), Err(SelectorParseError::InvalidSerial));
+        let group = Selector::Group(" ".to_string());
+        assert_eq!(group.validate(), Err(SelectorParseError::NoValue));
+    }
+    #[test]
+    fn round_trip() {
+        let selector = Selector::Label("Strip".to_string()).zoned(0..3);
+        let json = serde_json::to_string(&selector).expect("Failed to serialize selector.");
+        assert_eq!(json, r#""label:Strip|0|1|2""#);
+        let parsed: Zoned = serde_json::from_str(&json).expect("Failed to deserialize selector.");
+        assert_eq!(parsed, selector);
+        let selector = Selector::Location("Home".to_string()).random();
+        let json = serde_json::to_string(&selector).expect("Failed to serialize selector.");
+        let parsed: Random<Selector> =
+            serde_json::from_str(&json).expect("Failed to deserialize selector.");
+        assert_eq!(parsed, selector);
+        let selector = Selector::All.zoned(7).random();
+        let json = serde_json::to_string(&selector).expect("Failed to serialize selector.");
+        let parsed: Random<Zoned> =
+            serde_json::from_str(&json).expect("Failed to deserialize selector.");
+        assert_eq!(parsed, selector);
     }
 }
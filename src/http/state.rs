@@ -1,3 +1,4 @@
+use std::cmp::Ordering;
 use std::fmt;
 use std::num::{ParseFloatError, ParseIntError};
 use std::str::FromStr;
@@ -5,13 +6,36 @@ use std::time::Duration as StdDuration;
 
 use serde::{de::Error as DeError, Deserialize, Deserializer, Serialize, Serializer};
 
+use crate::http::{client::SetStatePayload, light::Light};
+
 /// Specifies the desired color setting of a light.
 ///
 /// HSBK is the preferred method of specifying colors (RGB represents color poorly); as such,
 /// `Hue`, `Saturation`, `Brightness`, and `Kelvin` are among the more useful variants here.
 ///
 /// RGB colors will automatically be converted by the API.
-#[derive(Clone, Debug, PartialEq)]
+///
+/// ## Ordering
+/// Colors have a total (presentational) ordering: first by variant, in declaration order (so
+/// named colors come first, then single components, HSBK, RGB, RGB strings, and custom strings),
+/// then by their contents. Missing HSBK components sort before present ones, and NaN sorts after
+/// every other number (and equal to itself).
+///
+/// ```
+/// use lifxi::http::Color;
+/// let mut colors = vec![
+///     Color::Rgb([0, 0, 255]),
+///     Color::Hue(240),
+///     Color::Red,
+///     Color::Hue(120),
+/// ];
+/// colors.sort();
+/// assert_eq!(
+///     colors,
+///     vec![Color::Red, Color::Hue(120), Color::Hue(240), Color::Rgb([0, 0, 255])]
+/// );
+/// ```
+#[derive(Clone, Debug)]
 pub enum Color {
     /// Sets the hue and saturation components necessary to change the color to red, leaving
     /// brightness untouched.
@@ -39,7 +63,8 @@ pub enum Color {
     White,
     /// Sets the hue, leaving all else untouched.
     ///
-    /// The hue should be between 0 and 360.
+    /// The hue is in degrees, and should be between 0 and 360. To specify a hue as a fraction of
+    /// the color wheel (0–1) instead, use [`Color::hue_fraction`](#method.hue_fraction).
     Hue(u16),
     /// Sets the saturation, leaving all else untouched.
     ///
@@ -75,6 +100,162 @@ pub enum Color {
     Custom(String),
 }
 
+/// One of the named color presets understood by the API.
+///
+/// See [`Color::nearest_named`](enum.Color.html#method.nearest_named).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum NamedColor {
+    /// Red (hue 0°).
+    Red,
+    /// Orange (hue 36°).
+    Orange,
+    /// Yellow (hue 60°).
+    Yellow,
+    /// Green (hue 120°).
+    Green,
+    /// Blue (hue 250°).
+    Blue,
+    /// Purple (hue 280°).
+    Purple,
+    /// Pink (hue 325°).
+    Pink,
+    /// White (no saturation).
+    White,
+}
+
+impl NamedColor {
+    const ALL: [Self; 8] = [
+        Self::Red,
+        Self::Orange,
+        Self::Yellow,
+        Self::Green,
+        Self::Blue,
+        Self::Purple,
+        Self::Pink,
+        Self::White,
+    ];
+    /// The hue (in degrees) and saturation of the preset.
+    fn hue_saturation(self) -> (f32, f32) {
+        match self {
+            Self::Red => (0.0, 1.0),
+            Self::Orange => (36.0, 1.0),
+            Self::Yellow => (60.0, 1.0),
+            Self::Green => (120.0, 1.0),
+            Self::Blue => (250.0, 1.0),
+            Self::Purple => (280.0, 1.0),
+            Self::Pink => (325.0, 1.0),
+            Self::White => (0.0, 0.0),
+        }
+    }
+}
+
+impl From<NamedColor> for Color {
+    fn from(color: NamedColor) -> Self {
+        match color {
+            NamedColor::Red => Self::Red,
+            NamedColor::Orange => Self::Orange,
+            NamedColor::Yellow => Self::Yellow,
+            NamedColor::Green => Self::Green,
+            NamedColor::Blue => Self::Blue,
+            NamedColor::Purple => Self::Purple,
+            NamedColor::Pink => Self::Pink,
+            NamedColor::White => Self::White,
+        }
+    }
+}
+
+/// Converts an RGB color to its hue (in degrees) and saturation, ignoring brightness.
+fn rgb_hue_saturation([r, g, b]: [u8; 3]) -> (f32, f32) {
+    let [r, g, b] = [f32::from(r), f32::from(g), f32::from(b)];
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    if delta == 0.0 {
+        return (0.0, 0.0);
+    }
+    let hue = if (max - r).abs() < f32::EPSILON {
+        ((g - b) / delta).rem_euclid(6.0)
+    } else if (max - g).abs() < f32::EPSILON {
+        (b - r) / delta + 2.0
+    } else {
+        (r - g) / delta + 4.0
+    };
+    (hue * 60.0, delta / max)
+}
+
+/// Compares two floats, treating NaN as greater than (and only equal to) itself.
+fn total_cmp(a: f32, b: f32) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+    }
+}
+
+fn option_cmp(a: Option<f32>, b: Option<f32>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => total_cmp(a, b),
+        (a, b) => a.is_some().cmp(&b.is_some()),
+    }
+}
+
+impl Color {
+    /// The position of the variant in the declaration order.
+    fn rank(&self) -> u8 {
+        match self {
+            Self::Red => 0,
+            Self::Orange => 1,
+            Self::Yellow => 2,
+            Self::Green => 3,
+            Self::Blue => 4,
+            Self::Purple => 5,
+            Self::Pink => 6,
+            Self::White => 7,
+            Self::Hue(_) => 8,
+            Self::Saturation(_) => 9,
+            Self::Brightness(_) => 10,
+            Self::Kelvin(_) => 11,
+            Self::Hsbk(..) => 12,
+            Self::Rgb(_) => 13,
+            Self::RgbStr(_) => 14,
+            Self::Custom(_) => 15,
+        }
+    }
+}
+
+impl Ord for Color {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Hue(a), Self::Hue(b)) | (Self::Kelvin(a), Self::Kelvin(b)) => a.cmp(b),
+            (Self::Saturation(a), Self::Saturation(b))
+            | (Self::Brightness(a), Self::Brightness(b)) => total_cmp(*a, *b),
+            (Self::Hsbk(h1, s1, b1, k1), Self::Hsbk(h2, s2, b2, k2)) => h1
+                .cmp(h2)
+                .then_with(|| option_cmp(*s1, *s2))
+                .then_with(|| option_cmp(*b1, *b2))
+                .then_with(|| k1.cmp(k2)),
+            (Self::Rgb(a), Self::Rgb(b)) => a.cmp(b),
+            (Self::RgbStr(a), Self::RgbStr(b)) | (Self::Custom(a), Self::Custom(b)) => a.cmp(b),
+            (a, b) => a.rank().cmp(&b.rank()),
+        }
+    }
+}
+
+impl PartialOrd for Color {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Color {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Color {}
+
 impl fmt::Display for Color {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -104,18 +285,99 @@ impl fmt::Display for Color {
                 write!(f, "{}", s)
             }
             Color::Rgb(rgb) => write!(f, "rgb:{},{},{}", rgb[0], rgb[1], rgb[2]),
-            Color::RgbStr(s) => {
-                if s.starts_with('#') {
-                    write!(f, "{}", s)
-                } else {
-                    write!(f, "#{}", s)
-                }
-            }
+            Color::RgbStr(s) => write!(f, "#{}", expand_hex(s.trim_start_matches('#'))),
             Color::Custom(s) => write!(f, "{}", s),
         }
     }
 }
 
+/// Guides construction of a multi-component color.
+///
+/// Created by [`Color::builder`](enum.Color.html#method.builder). Choosing an HSBK component
+/// yields an [`HsbkBuilder`](struct.HsbkBuilder.html), which has no way back to RGB, so mixing
+/// the two color models is a compile error:
+///
+/// ```compile_fail
+/// use lifxi::http::Color;
+/// let color = Color::builder().hue(120).rgb([255, 0, 0]);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ColorBuilder;
+
+impl ColorBuilder {
+    /// Specifies an RGB color, finishing the builder.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::Color;
+    /// assert_eq!(Color::builder().rgb([0, 128, 255]), Color::Rgb([0, 128, 255]));
+    /// ```
+    pub fn rgb(self, rgb: [u8; 3]) -> Color {
+        Color::Rgb(rgb)
+    }
+    /// Sets the hue (0–360), continuing with HSBK components.
+    pub fn hue(self, hue: u16) -> HsbkBuilder {
+        HsbkBuilder::default().hue(hue)
+    }
+    /// Sets the saturation (0–1), continuing with HSBK components.
+    pub fn saturation(self, saturation: f32) -> HsbkBuilder {
+        HsbkBuilder::default().saturation(saturation)
+    }
+    /// Sets the brightness (0–1), continuing with HSBK components.
+    pub fn brightness(self, brightness: f32) -> HsbkBuilder {
+        HsbkBuilder::default().brightness(brightness)
+    }
+    /// Sets the color temperature (1500–9000 K), continuing with HSBK components.
+    pub fn kelvin(self, kelvin: u16) -> HsbkBuilder {
+        HsbkBuilder::default().kelvin(kelvin)
+    }
+}
+
+/// Builds a color from hue, saturation, brightness, and color temperature components.
+///
+/// ## Example
+/// ```
+/// use lifxi::http::Color;
+/// let color = Color::builder().hue(120).saturation(0.5).kelvin(3500).build();
+/// assert_eq!(color, Color::Hsbk(Some(120), Some(0.5), None, Some(3500)));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct HsbkBuilder {
+    hue: Option<u16>,
+    saturation: Option<f32>,
+    brightness: Option<f32>,
+    kelvin: Option<u16>,
+}
+
+impl HsbkBuilder {
+    /// Sets the hue (0–360).
+    pub fn hue(mut self, hue: u16) -> Self {
+        self.hue = Some(hue);
+        self
+    }
+    /// Sets the saturation (0–1).
+    pub fn saturation(mut self, saturation: f32) -> Self {
+        self.saturation = Some(saturation);
+        self
+    }
+    /// Sets the brightness (0–1).
+    pub fn brightness(mut self, brightness: f32) -> Self {
+        self.brightness = Some(brightness);
+        self
+    }
+    /// Sets the color temperature (1500–9000 K).
+    pub fn kelvin(mut self, kelvin: u16) -> Self {
+        self.kelvin = Some(kelvin);
+        self
+    }
+    /// Produces the combined color.
+    ///
+    /// The components are not validated here; see [`Color::validate`](enum.Color.html#method.validate).
+    pub fn build(self) -> Color {
+        Color::Hsbk(self.hue, self.saturation, self.brightness, self.kelvin)
+    }
+}
+
 /// Represents an error encountered while deserializing a color.
 #[derive(Clone, Debug, PartialEq)]
 pub enum ColorParseError {
@@ -173,6 +435,15 @@ pub enum ColorParseError {
     /// assert!(color.is_err());
     /// ```
     NonNumericBrightness(ParseFloatError),
+    /// A saturation or brightness percentage was outside of the range 0–100%.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// let color = "brightness:150%".parse::<Color>();
+    /// assert_eq!(color, Err(ColorParseError::PercentageOutOfRange(150.0)));
+    /// ```
+    PercentageOutOfRange(f32),
     /// No color temperature was given.
     ///
     /// ## Example
@@ -296,7 +567,7 @@ pub enum ColorParseError {
     /// ## Example
     /// ```
     /// use lifxi::http::prelude::*;
-    /// let color = "foo".parse::<Color>();
+    /// let color = "fo".parse::<Color>();
     /// assert_eq!(color, Err(ColorParseError::ShortString));
     /// ```
     ShortString,
@@ -309,6 +580,16 @@ pub enum ColorParseError {
     /// assert_eq!(color, Err(ColorParseError::LongString));
     /// ```
     LongString,
+    /// The string is the length of an RGB string, but contains characters which aren't hex
+    /// digits (and was not recognized as a keyword).
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// let color = "#12z456".parse::<Color>();
+    /// assert_eq!(color, Err(ColorParseError::NonHexString));
+    /// ```
+    NonHexString,
 }
 
 impl fmt::Display for ColorParseError {
@@ -321,6 +602,7 @@ impl fmt::Display for ColorParseError {
             NonNumericSaturation(e) => write!(f, "Failed to parse saturation as float: {}", e),
             NoBrightness => write!(f, "Expected brightness after brightness: label."),
             NonNumericBrightness(e) => write!(f, "Failed to parse brightness as float: {}", e),
+            PercentageOutOfRange(p) => write!(f, "Percentage {}% is not between 0 and 100%.", p),
             NoKelvin => write!(f, "Expected color temperature after kelvin: label."),
             NonNumericKelvin(e) => write!(f, "Failed to parse color temperature as integer: {}", e),
             WeirdHsbkComponent(c) => write!(f, "Found another color while parsing as HSBK: {}", c),
@@ -342,14 +624,77 @@ impl fmt::Display for ColorParseError {
                 f,
                 "String is too long to be an RGB string and was not recognized as a keyword."
             ),
+            NonHexString => write!(
+                f,
+                "String contains non-hex digits and was not recognized as a keyword."
+            ),
         }
     }
 }
 
+/// Parses either a fraction (`0.5`) or a percentage (`50%`), returning the fraction.
+fn fraction(
+    spec: &str,
+    non_numeric: fn(ParseFloatError) -> ColorParseError,
+) -> Result<f32, ColorParseError> {
+    match spec.strip_suffix('%') {
+        Some(percentage) => {
+            let percentage = percentage.parse::<f32>().map_err(non_numeric)?;
+            if (0.0..=100.0).contains(&percentage) {
+                Ok(percentage / 100.0)
+            } else {
+                Err(ColorParseError::PercentageOutOfRange(percentage))
+            }
+        }
+        None => spec.parse().map_err(non_numeric),
+    }
+}
+
+/// Computes `1_000_000 / value`, rounded to the nearest integer and saturating at `u16::MAX`.
+fn reciprocal(value: u16) -> u16 {
+    let value = u32::from(value);
+    let result = (1_000_000 + value / 2) / value;
+    result.min(u32::from(u16::MAX)) as u16
+}
+
+/// Whether every character of the string is a hex digit.
+fn is_hex(digits: &str) -> bool {
+    digits.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Expands three-digit (`rgb`) hex shorthand to the six-digit (`rrggbb`) form, doubling each
+/// digit; anything else is returned unchanged.
+fn expand_hex(digits: &str) -> String {
+    if digits.len() == 3 && is_hex(digits) {
+        digits.chars().flat_map(|c| vec![c, c]).collect()
+    } else {
+        digits.to_string()
+    }
+}
+
+/// Parses an eight-digit (`rrggbbaa`) hex string, discarding the alpha channel.
+fn rgba_hex(digits: &str) -> Option<Color> {
+    if digits.len() != 8 || !is_hex(digits) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).ok();
+    Some(Color::Rgb([channel(0)?, channel(2)?, channel(4)?]))
+}
+
 impl FromStr for Color {
     type Err = ColorParseError;
     /// Parses the color string into a color setting.
     ///
+    /// Saturation and brightness may be given either as fractions (`brightness:0.5`) or as
+    /// percentages (`brightness:50%`).
+    ///
+    /// Eight-digit hex strings (`#rrggbbaa` or `rrggbbaa`, as produced by some color pickers) are
+    /// accepted as RGB colors; the alpha channel is silently discarded, since bulbs have no
+    /// transparency.
+    ///
+    /// Three-digit hex shorthand (`#f00` or `f00`) is expanded to the equivalent six-digit form
+    /// (`#ff0000` or `ff0000`).
+    ///
     /// ## Notes
     /// Custom colors cannot be made with this method; use `Color::Custom(s)` instead.
     #[allow(clippy::cyclomatic_complexity)]
@@ -417,8 +762,7 @@ impl FromStr for Color {
                     if spec.trim().is_empty() {
                         Err(NoSaturation)
                     } else {
-                        let s = spec.parse();
-                        s.map(Saturation).map_err(NonNumericSaturation)
+                        fraction(spec, NonNumericSaturation).map(Saturation)
                     }
                 } else {
                     Err(NoSaturation)
@@ -429,8 +773,7 @@ impl FromStr for Color {
                     if spec.trim().is_empty() {
                         Err(NoBrightness)
                     } else {
-                        let b = spec.parse();
-                        b.map(Brightness).map_err(NonNumericBrightness)
+                        fraction(spec, NonNumericBrightness).map(Brightness)
                     }
                 } else {
                     Err(NoBrightness)
@@ -491,14 +834,18 @@ impl FromStr for Color {
             s => {
                 if s.starts_with('#') {
                     match s.len() {
+                        4 | 7 if is_hex(&s[1..]) => Ok(RgbStr(format!("#{}", expand_hex(&s[1..])))),
+                        4 | 7 => Err(NonHexString),
                         x if x < 7 => Err(ShortString),
-                        7 => Ok(RgbStr(s.to_string())),
+                        9 => s.strip_prefix('#').and_then(rgba_hex).ok_or(LongString),
                         _ => Err(LongString),
                     }
                 } else {
                     match s.len() {
+                        3 | 6 if is_hex(s) => Ok(RgbStr(expand_hex(s))),
+                        3 | 6 => Err(NonHexString),
                         x if x < 6 => Err(ShortString),
-                        6 => Ok(RgbStr(s.to_string())),
+                        8 => rgba_hex(s).ok_or(LongString),
                         _ => Err(LongString),
                     }
                 }
@@ -519,6 +866,15 @@ pub enum Error {
     /// assert_eq!(res, Err(ColorValidationError::Hue(361)));
     /// ```
     Hue(u16),
+    /// The given hue fraction was outside of the range 0–1.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// let res = Color::hue_fraction(1.5);
+    /// assert_eq!(res, Err(ColorValidationError::HueFraction(1.5)));
+    /// ```
+    HueFraction(f32),
     /// The given saturation was greater than 1.0.
     ///
     /// ## Example
@@ -604,43 +960,207 @@ pub enum Error {
     /// assert_eq!(res, Err(ColorValidationError::RgbStrLong(true, "#1234567".to_string())));
     /// ```
     RgbStrLong(bool, String),
+    /// The given RGB string contained characters which aren't hex digits.
+    ///
+    /// ## Examples
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// let res = Color::RgbStr("#12z456".to_string()).validate();
+    /// assert_eq!(res, Err(ColorValidationError::RgbStrInvalidChar(true, "#12z456".to_string())));
+    /// let res = Color::RgbStr("zzzzzz".to_string()).validate();
+    /// assert_eq!(res, Err(ColorValidationError::RgbStrInvalidChar(false, "zzzzzz".to_string())));
+    /// ```
+    RgbStrInvalidChar(bool, String),
+    /// The given level was greater than 1.0.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// use lifxi::http::Level;
+    /// assert_eq!(Level::new(1.5), Err(ColorValidationError::LevelHigh(1.5)));
+    /// ```
+    LevelHigh(f32),
+    /// The given level was less than 0.0 (or not a number).
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// use lifxi::http::Level;
+    /// assert_eq!(Level::new(-0.5), Err(ColorValidationError::LevelLow(-0.5)));
+    /// ```
+    LevelLow(f32),
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Error::Hue(hue) => write!(f, "Hue {} is too large (max: 360).", hue),
-            Error::SaturationHigh(sat) => write!(f, "Saturation {} is too large (max: 1.0).", sat),
-            Error::SaturationLow(sat) => write!(f, "Saturation {} is negative.", sat),
-            Error::BrightnessHigh(b) => write!(f, "Brightness {} is too large (max: 1.0).", b),
-            Error::BrightnessLow(b) => write!(f, "Brightness {} is negative.", b),
-            Error::KelvinHigh(t) => write!(f, "Temperature {} K is too large (max: 9000 K).", t),
-            Error::KelvinLow(t) => write!(f, "Temperature {} K is too small (min: 1500 K).", t),
-            Error::HsbkEmpty => write!(
+            Self::Hue(hue) => write!(f, "Hue {} is too large (max: 360).", hue),
+            Self::HueFraction(hue) => write!(f, "Hue fraction {} is not between 0 and 1.", hue),
+            Self::SaturationHigh(sat) => write!(f, "Saturation {} is too large (max: 1.0).", sat),
+            Self::SaturationLow(sat) => write!(f, "Saturation {} is negative.", sat),
+            Self::BrightnessHigh(b) => write!(f, "Brightness {} is too large (max: 1.0).", b),
+            Self::BrightnessLow(b) => write!(f, "Brightness {} is negative.", b),
+            Self::KelvinHigh(t) => write!(f, "Temperature {} K is too large (max: 9000 K).", t),
+            Self::KelvinLow(t) => write!(f, "Temperature {} K is too small (min: 1500 K).", t),
+            Self::HsbkEmpty => write!(
                 f,
                 "No hue, saturation, brightness, or color temperature given."
             ),
-            Error::RgbStrShort(h, s) => write!(
+            Self::RgbStrShort(h, s) => write!(
                 f,
                 "RGB string {} is too short ({} chars; expected {}).",
                 s,
                 s.len(),
-                if *h { 7 } else { 6 }
+                if *h { "4 or 7" } else { "3 or 6" }
             ),
-            Error::RgbStrLong(h, s) => write!(
+            Self::RgbStrLong(h, s) => write!(
                 f,
                 "RGB string {} is too long ({} chars; expected {}).",
                 s,
                 s.len(),
                 if *h { 7 } else { 6 }
             ),
+            Self::RgbStrInvalidChar(_, s) => {
+                write!(
+                    f,
+                    "RGB string {} contains characters which aren't hex digits.",
+                    s
+                )
+            }
+            Self::LevelHigh(l) => write!(f, "Level {} is too large (max: 1.0).", l),
+            Self::LevelLow(l) => write!(f, "Level {} is negative.", l),
         }
     }
 }
 
 impl ::std::error::Error for Error {}
 
+/// A color temperature, validated to lie within the range supported by the API.
+///
+/// ## Example
+/// ```
+/// use lifxi::http::prelude::*;
+/// use lifxi::http::Kelvin;
+/// let warm = Kelvin::new(2700).expect("2700 K is valid.");
+/// assert_eq!(Color::from(warm), Color::Kelvin(2700));
+/// assert_eq!(Kelvin::new(9001), Err(ColorValidationError::KelvinHigh(9001)));
+/// assert_eq!(Kelvin::new(1499), Err(ColorValidationError::KelvinLow(1499)));
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Kelvin(u16);
+
+impl Kelvin {
+    /// The lowest temperature accepted by the API.
+    pub const MIN: u16 = 1500;
+    /// The highest temperature accepted by the API.
+    pub const MAX: u16 = 9000;
+    /// Creates a new temperature, checking that it lies between 1500 and 9000 K (inclusive).
+    pub fn new(kelvin: u16) -> Result<Self, Error> {
+        if kelvin < Self::MIN {
+            Err(Error::KelvinLow(kelvin))
+        } else if kelvin > Self::MAX {
+            Err(Error::KelvinHigh(kelvin))
+        } else {
+            Ok(Self(kelvin))
+        }
+    }
+    /// The temperature, in Kelvin.
+    pub fn get(self) -> u16 {
+        self.0
+    }
+}
+
+impl From<Kelvin> for u16 {
+    fn from(kelvin: Kelvin) -> Self {
+        kelvin.0
+    }
+}
+
+impl From<Kelvin> for Color {
+    fn from(kelvin: Kelvin) -> Self {
+        Self::Kelvin(kelvin.0)
+    }
+}
+
+/// A fraction between 0 and 1, used for brightness, saturation, infrared, and effect peaks.
+///
+/// [`Level::new`](#method.new) rejects values outside of that range. Builder methods accept
+/// anything that converts into a `Level`, and the conversion from `f32` clamps instead, so
+/// passing `1.2` sends `1.0` and passing `-0.3` (or NaN) sends `0.0`. Use `Level::new` first to
+/// catch out-of-range values rather than silently correcting them.
+///
+/// ## Example
+/// ```
+/// use lifxi::http::prelude::*;
+/// use lifxi::http::Level;
+/// let half = Level::new(0.5).expect("0.5 is valid.");
+/// assert_eq!(half.get(), 0.5);
+/// assert_eq!(Level::from(1.2).get(), 1.0);
+/// assert_eq!(Level::from(-0.3).get(), 0.0);
+/// assert_eq!(Level::new(1.2), Err(ColorValidationError::LevelHigh(1.2)));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct Level(f32);
+
+impl Level {
+    /// The lowest level.
+    pub const MIN: f32 = 0.0;
+    /// The highest level.
+    pub const MAX: f32 = 1.0;
+    /// Creates a new level, checking that it lies between 0 and 1 (inclusive).
+    pub fn new(level: f32) -> Result<Self, Error> {
+        if level > Self::MAX {
+            Err(Error::LevelHigh(level))
+        } else if level >= Self::MIN {
+            Ok(Self(level))
+        } else {
+            Err(Error::LevelLow(level))
+        }
+    }
+    /// The level, as a fraction.
+    pub fn get(self) -> f32 {
+        self.0
+    }
+}
+
+impl From<f32> for Level {
+    fn from(level: f32) -> Self {
+        if level.is_nan() {
+            Self(Self::MIN)
+        } else {
+            Self(level.clamp(Self::MIN, Self::MAX))
+        }
+    }
+}
+
+impl From<Level> for f32 {
+    fn from(level: Level) -> Self {
+        level.0
+    }
+}
+
 impl Color {
+    /// Creates a builder for a multi-component (HSBK) or RGB color.
+    ///
+    /// See [`ColorBuilder`](struct.ColorBuilder.html).
+    pub fn builder() -> ColorBuilder {
+        ColorBuilder
+    }
+    /// Creates a fully-specified HSBK color from all four components.
+    ///
+    /// This is shorthand for [`Color::Hsbk`](#variant.Hsbk) with every component set; use
+    /// [`builder`](#method.builder) to set only some of them.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::Color;
+    /// let color = Color::hsbk(120, 0.5, 0.75, 3500);
+    /// assert_eq!(color, Color::Hsbk(Some(120), Some(0.5), Some(0.75), Some(3500)));
+    /// assert_eq!(&format!("{}", color), "hue:120 saturation:0.5 brightness:0.75 kelvin:3500");
+    /// ```
+    pub fn hsbk(hue: u16, saturation: f32, brightness: f32, kelvin: u16) -> Self {
+        Self::Hsbk(Some(hue), Some(saturation), Some(brightness), Some(kelvin))
+    }
     /// Checks whether the color is valid.
     ///
     /// ## Notes
@@ -699,28 +1219,18 @@ impl Color {
                     Ok(())
                 }
             }
-            Kelvin(t) => {
-                if *t < 1500 {
-                    Err(KelvinLow(*t))
-                } else if *t > 9000 {
-                    Err(KelvinHigh(*t))
-                } else {
-                    Ok(())
-                }
-            }
+            Self::Kelvin(t) => self::Kelvin::new(*t).map(|_| ()),
             RgbStr(s) => {
-                if s.starts_with('#') {
-                    if s.len() > 7 {
-                        Err(RgbStrLong(true, s.clone()))
-                    } else if s.len() < 7 {
-                        Err(RgbStrShort(true, s.clone()))
-                    } else {
-                        Ok(())
-                    }
-                } else if s.len() > 6 {
-                    Err(RgbStrLong(false, s.clone()))
-                } else if s.len() < 6 {
-                    Err(RgbStrShort(false, s.clone()))
+                let (hash, digits) = match s.strip_prefix('#') {
+                    Some(digits) => (true, digits),
+                    None => (false, s.as_str()),
+                };
+                if digits.len() > 6 {
+                    Err(RgbStrLong(hash, s.clone()))
+                } else if digits.len() != 6 && digits.len() != 3 {
+                    Err(RgbStrShort(hash, s.clone()))
+                } else if !is_hex(digits) {
+                    Err(RgbStrInvalidChar(hash, s.clone()))
                 } else {
                     Ok(())
                 }
@@ -746,11 +1256,194 @@ impl Color {
             }
         }
     }
+    /// Creates a hue setting from a fraction (0–1) of the color wheel, as given by many color
+    /// pickers.
+    ///
+    /// The fraction is scaled to degrees and rounded, so 0.5 becomes `Color::Hue(180)`. Passing a
+    /// fraction directly to [`Color::Hue`](#variant.Hue) (which takes degrees) is a common mistake.
+    ///
+    /// ## Examples
+    /// ```
+    /// use lifxi::http::Color;
+    /// assert_eq!(Color::hue_fraction(0.5), Ok(Color::Hue(180)));
+    /// assert!(Color::hue_fraction(-0.1).is_err());
+    /// ```
+    pub fn hue_fraction(fraction: f32) -> Result<Self, Error> {
+        if (0.0..=1.0).contains(&fraction) {
+            Ok(Self::Hue((fraction * 360.0).round() as u16))
+        } else {
+            Err(Error::HueFraction(fraction))
+        }
+    }
+    /// Creates a color temperature setting from a temperature in mireds (micro reciprocal
+    /// degrees), as used by some integrations (e.g., HomeKit).
+    ///
+    /// The temperature is converted to Kelvin (`1_000_000 / mired`, rounded) and then validated;
+    /// the accepted range is therefore roughly 112–666 mireds.
+    ///
+    /// ## Examples
+    /// ```
+    /// use lifxi::http::Color;
+    /// assert_eq!(Color::from_mired(370), Ok(Color::Kelvin(2703)));
+    /// assert!(Color::from_mired(100).is_err());
+    /// ```
+    pub fn from_mired(mired: u16) -> Result<Self, Error> {
+        let kelvin = match mired {
+            0 => u16::MAX,
+            m => reciprocal(m),
+        };
+        self::Kelvin::new(kelvin).map(Self::from)
+    }
+    /// Converts a color temperature in Kelvin to mireds (micro reciprocal degrees), rounding to
+    /// the nearest mired.
+    ///
+    /// Mireds are much coarser than Kelvin at low values, so converting back (with
+    /// [`from_mired`](#method.from_mired)) may not give exactly the original temperature.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::Color;
+    /// assert_eq!(Color::kelvin_to_mired(2700), 370);
+    /// ```
+    pub fn kelvin_to_mired(kelvin: u16) -> u16 {
+        match kelvin {
+            0 => u16::MAX,
+            k => reciprocal(k),
+        }
+    }
+    /// Converts an `Rgb` color to the equivalent `RgbStr` (`#rrggbb`) form, leaving other colors
+    /// unchanged.
+    ///
+    /// Both forms describe the same color; this only changes how it is sent to the API.
+    ///
+    /// ## Examples
+    /// ```
+    /// use lifxi::http::Color;
+    /// let color = Color::Rgb([255, 128, 0]).rgb_as_hex();
+    /// assert_eq!(color, Color::RgbStr("#ff8000".to_string()));
+    /// assert_eq!(&format!("{}", color), "#ff8000");
+    /// assert_eq!(Color::Red.rgb_as_hex(), Color::Red);
+    /// ```
+    pub fn rgb_as_hex(&self) -> Self {
+        match self {
+            Self::Rgb([r, g, b]) => Self::RgbStr(format!("#{:02x}{:02x}{:02x}", r, g, b)),
+            color => color.clone(),
+        }
+    }
+    /// Finds the named preset closest to this color, along with its distance.
+    ///
+    /// Colors are compared by hue and saturation only (as points on the color wheel, with white
+    /// at the center), so the distance ranges from 0 (identical) to 2 (opposite hues). Brightness
+    /// is ignored. A bare hue is taken to be fully saturated, and a bare color temperature to be
+    /// white. Returns `None` for colors which can't be placed on the wheel (custom colors, bare
+    /// saturations or brightnesses, and malformed hex strings).
+    ///
+    /// ## Examples
+    /// ```
+    /// use lifxi::http::{Color, NamedColor};
+    /// let (name, distance) = Color::Rgb([255, 140, 0]).nearest_named().unwrap();
+    /// assert_eq!(name, NamedColor::Orange);
+    /// assert!(distance < 0.2);
+    /// assert_eq!(Color::Kelvin(2700).nearest_named(), Some((NamedColor::White, 0.0)));
+    /// assert_eq!(Color::Brightness(0.5).nearest_named(), None);
+    /// ```
+    pub fn nearest_named(&self) -> Option<(NamedColor, f32)> {
+        let (hue, saturation) = match self {
+            Self::Red => NamedColor::Red.hue_saturation(),
+            Self::Orange => NamedColor::Orange.hue_saturation(),
+            Self::Yellow => NamedColor::Yellow.hue_saturation(),
+            Self::Green => NamedColor::Green.hue_saturation(),
+            Self::Blue => NamedColor::Blue.hue_saturation(),
+            Self::Purple => NamedColor::Purple.hue_saturation(),
+            Self::Pink => NamedColor::Pink.hue_saturation(),
+            Self::White | Self::Kelvin(_) => NamedColor::White.hue_saturation(),
+            Self::Hue(h) => (f32::from(*h), 1.0),
+            Self::Hsbk(h, s, _, k) => match (h, s) {
+                (Some(h), s) => (f32::from(*h), s.unwrap_or(1.0)),
+                (None, Some(s)) if *s == 0.0 => (0.0, 0.0),
+                (None, None) if k.is_some() => (0.0, 0.0),
+                _ => return None,
+            },
+            Self::Rgb(rgb) => rgb_hue_saturation(*rgb),
+            Self::RgbStr(s) => {
+                match rgba_hex(&format!("{}ff", expand_hex(s.trim_start_matches('#')))) {
+                    Some(Self::Rgb(rgb)) => rgb_hue_saturation(rgb),
+                    _ => return None,
+                }
+            }
+            Self::Saturation(_) | Self::Brightness(_) | Self::Custom(_) => return None,
+        };
+        let point = |hue: f32, saturation: f32| {
+            let angle = hue.to_radians();
+            (saturation * angle.cos(), saturation * angle.sin())
+        };
+        let (x, y) = point(hue, saturation.clamp(0.0, 1.0));
+        NamedColor::ALL
+            .iter()
+            .map(|&named| {
+                let (h, s) = named.hue_saturation();
+                let (nx, ny) = point(h, s);
+                (named, (x - nx).hypot(y - ny))
+            })
+            .min_by(|a, b| total_cmp(a.1, b.1))
+    }
+    /// Checks whether two colors are equal, allowing floating-point components to differ by up
+    /// to `epsilon`.
+    ///
+    /// Integer and string components must match exactly, as must the variants themselves; this
+    /// is only useful for comparing colors of the same form (e.g. a requested saturation against
+    /// the saturation reported by the API).
+    ///
+    /// ## Examples
+    /// ```
+    /// use lifxi::http::Color;
+    /// let requested = Color::Saturation(0.1);
+    /// let reported = Color::Saturation(0.099_999);
+    /// assert!(requested != reported);
+    /// assert!(requested.approx_eq(&reported, 0.001));
+    /// let requested = Color::Hsbk(Some(120), Some(0.5), None, None);
+    /// let reported = Color::Hsbk(Some(121), Some(0.5), None, None);
+    /// assert!(!requested.approx_eq(&reported, 0.001));
+    /// ```
+    pub fn approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+        use self::Color::*;
+        let close = |a: f32, b: f32| (a - b).abs() <= epsilon;
+        let both = |a: &Option<f32>, b: &Option<f32>| match (a, b) {
+            (Some(a), Some(b)) => close(*a, *b),
+            (None, None) => true,
+            _ => false,
+        };
+        match (self, other) {
+            (Saturation(a), Saturation(b)) | (Brightness(a), Brightness(b)) => close(*a, *b),
+            (Hsbk(h1, s1, b1, k1), Hsbk(h2, s2, b2, k2)) => {
+                h1 == h2 && k1 == k2 && both(s1, s2) && both(b1, b2)
+            }
+            (a, b) => a == b,
+        }
+    }
 }
 
 /// A thin wrapper for `std::time::Duration` to aid with {de,}serialization.
 #[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
-pub struct Duration(StdDuration);
+pub struct Duration(pub(crate) StdDuration);
+
+impl Duration {
+    /// The duration, in (fractional) seconds.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::State;
+    /// let state: State = serde_json::from_str(r#"{ "duration": 1.5 }"#).unwrap();
+    /// assert_eq!(state.duration.map(|d| d.as_secs_f64()), Some(1.5));
+    /// ```
+    pub fn as_secs_f64(&self) -> f64 {
+        self.0.as_secs_f64()
+    }
+    /// The wrapped `std::time::Duration`.
+    pub fn inner(&self) -> StdDuration {
+        self.0
+    }
+}
 
 impl From<StdDuration> for Duration {
     fn from(duration: StdDuration) -> Self {
@@ -773,7 +1466,7 @@ impl From<bool> for Power {
 /// This struct should only be used directly when using
 /// [`Selected::set_states`](struct.Selected.html#method.set_states), and even then, it is
 /// encouraged to use the builder methods instead of directly constructing a set of changes.
-#[derive(Clone, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct State {
     /// The desired power state, if appropriate.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -793,10 +1486,36 @@ pub struct State {
     pub infrared: Option<f32>,
 }
 
+/// The forms in which the API represents colors: strings when specifying, and HSBK objects when
+/// reporting (e.g., in scene states).
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ColorRepr {
+    Str(String),
+    Hsbk {
+        hue: Option<f32>,
+        saturation: Option<f32>,
+        brightness: Option<f32>,
+        kelvin: Option<u16>,
+    },
+}
+
 impl<'de> Deserialize<'de> for Color {
     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<(Self), D::Error> {
-        let s = String::deserialize(deserializer)?;
-        s.parse::<Self>().map_err(DeError::custom)
+        match ColorRepr::deserialize(deserializer)? {
+            ColorRepr::Str(s) => s.parse::<Self>().map_err(DeError::custom),
+            ColorRepr::Hsbk {
+                hue,
+                saturation,
+                brightness,
+                kelvin,
+            } => Ok(Self::Hsbk(
+                hue.map(|hue| hue.round() as u16),
+                saturation,
+                brightness,
+                kelvin,
+            )),
+        }
     }
 }
 
@@ -878,6 +1597,22 @@ impl State {
         self.color = Some(color);
         self
     }
+    /// Builder function to set target color setting from a color string.
+    ///
+    /// Fails if the string isn't a valid color; see [`State::color`](#method.color) for the
+    /// infallible, typed equivalent.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::State;
+    /// let new = State::builder().color_str("orange");
+    /// assert!(new.is_ok());
+    /// let new = State::builder().color_str("hue:");
+    /// assert!(new.is_err());
+    /// ```
+    pub fn color_str(self, color: &str) -> Result<Self, ColorParseError> {
+        Ok(self.color(color.parse()?))
+    }
     /// Builder function to set target brightness setting.
     ///
     /// ## Example
@@ -886,8 +1621,8 @@ impl State {
     /// use lifxi::http::State;
     /// let new: State = State::builder().brightness(0.7).transition(Duration::from_millis(800));
     /// ```
-    pub fn brightness(mut self, brightness: f32) -> Self {
-        self.brightness = Some(brightness);
+    pub fn brightness<L: Into<Level>>(mut self, brightness: L) -> Self {
+        self.brightness = Some(brightness.into().get());
         self
     }
     /// Builder function to set animation duration.
@@ -909,10 +1644,25 @@ impl State {
     /// use lifxi::http::State;
     /// let new: State = State::builder().infrared(0.8);
     /// ```
-    pub fn infrared(mut self, infrared: f32) -> Self {
-        self.infrared = Some(infrared);
+    pub fn infrared<L: Into<Level>>(mut self, infrared: L) -> Self {
+        self.infrared = Some(infrared.into().get());
         self
     }
+    /// Produces the exact request body that
+    /// [`Selected::set_state`](struct.Selected.html#method.set_state) would send for this state.
+    ///
+    /// Useful for testing that a computed state serializes as expected without a live client.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::prelude::*;
+    /// let state = State::builder().power(true).brightness(0.5);
+    /// let json = serde_json::to_string(&state.to_payload(Some(true))).unwrap();
+    /// assert_eq!(json, r#"{"power":"on","brightness":0.5,"fast":true}"#);
+    /// ```
+    pub fn to_payload(&self, fast: Option<bool>) -> impl Serialize {
+        SetStatePayload::new(self.clone(), fast)
+    }
 }
 
 /// Encodes a desired state change.
@@ -920,7 +1670,7 @@ impl State {
 /// This struct is intended for use with
 /// [`Selected::change_state`](struct.Selected.html#method.change_state), and it is encouraged to
 /// use the builder methods instead of directly constructing a changeset.
-#[derive(Clone, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct StateChange {
     /// The desired power state.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -1022,6 +1772,82 @@ impl StateChange {
         self.kelvin = Some(temp);
         self
     }
+    /// Computes the absolute state which would result from applying this change to the given
+    /// light, without sending anything.
+    ///
+    /// Hue wraps around (so 350° + 20° is 10°), saturation and brightness are clamped to 0–1, and
+    /// the color temperature is clamped to 1500–9000 K. The resulting color always specifies hue,
+    /// saturation, and temperature; unchanged components are carried over from the light. An
+    /// infrared change is relative to the light's reported infrared level, or to zero for lights
+    /// which don't report one.
+    ///
+    /// ## Example
+    /// ```
+    /// use lifxi::http::{Color, StateChange};
+    /// # fn preview(light: &lifxi::http::Light) {
+    /// let after = StateChange::new().brightness(0.2).hue(20).apply(light);
+    /// if let Some(Color::Hsbk(Some(hue), _, _, _)) = after.color {
+    ///     println!("After: {}°, {}%", hue, after.brightness.unwrap_or(0.0) * 100.0);
+    /// }
+    /// # }
+    /// ```
+    pub fn apply(&self, base: &Light) -> State {
+        let clamp = |value: f32| value.clamp(0.0, 1.0);
+        let hue = (base.color.hue + f32::from(self.hue.unwrap_or(0))).rem_euclid(360.0);
+        let hue = hue.round() as u16 % 360;
+        let saturation = clamp(base.color.saturation + self.saturation.unwrap_or(0.0));
+        let kelvin = i32::from(base.color.kelvin) + i32::from(self.kelvin.unwrap_or(0));
+        let kelvin = kelvin
+            .max(i32::from(Kelvin::MIN))
+            .min(i32::from(Kelvin::MAX)) as u16;
+        State {
+            power: Some(self.power.unwrap_or(base.power)),
+            color: Some(Color::Hsbk(Some(hue), Some(saturation), None, Some(kelvin))),
+            brightness: Some(clamp(base.brightness + self.brightness.unwrap_or(0.0))),
+            duration: self.duration,
+            infrared: match self.infrared {
+                Some(delta) => Some(clamp(base.infrared.unwrap_or(0.0) + delta)),
+                None => base.infrared,
+            },
+        }
+    }
+}
+
+/// Composes two state changes into one.
+///
+/// Numeric deltas (hue, saturation, brightness, color temperature, and infrared) are summed, with
+/// hue and temperature saturating at the bounds of `i16`. The power state and duration are taken
+/// from the right-hand change when it specifies them, so a later power change wins over an
+/// earlier one.
+///
+/// ## Example
+/// ```
+/// use lifxi::http::StateChange;
+/// let first = StateChange::new().brightness(0.1).power(false);
+/// let second = StateChange::new().brightness(0.2).hue(30).power(true);
+/// let combined = first + second;
+/// assert_eq!(combined.hue, Some(30));
+/// assert!((combined.brightness.unwrap() - 0.3).abs() < 1e-6);
+/// ```
+impl ::std::ops::Add for StateChange {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        fn sum<T>(a: Option<T>, b: Option<T>, add: impl Fn(T, T) -> T) -> Option<T> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(add(a, b)),
+                (a, b) => a.or(b),
+            }
+        }
+        Self {
+            power: other.power.or(self.power),
+            duration: other.duration.or(self.duration),
+            infrared: sum(self.infrared, other.infrared, |a, b| a + b),
+            hue: sum(self.hue, other.hue, i16::saturating_add),
+            saturation: sum(self.saturation, other.saturation, |a, b| a + b),
+            brightness: sum(self.brightness, other.brightness, |a, b| a + b),
+            kelvin: sum(self.kelvin, other.kelvin, i16::saturating_add),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1030,6 +1856,20 @@ mod tests {
     mod state {
         use super::*;
         #[test]
+        fn levels() {
+            assert_eq!(Level::new(0.0).map(Level::get), Ok(0.0));
+            assert_eq!(Level::new(1.0).map(Level::get), Ok(1.0));
+            assert_eq!(Level::new(1.01), Err(Error::LevelHigh(1.01)));
+            assert_eq!(Level::new(-0.01), Err(Error::LevelLow(-0.01)));
+            assert!(Level::new(f32::NAN).is_err());
+            assert_eq!(Level::from(f32::NAN).get(), 0.0);
+            let state = State::new().brightness(1.5).infrared(-0.2);
+            assert_eq!(state.brightness, Some(1.0));
+            assert_eq!(state.infrared, Some(0.0));
+            let level = Level::new(0.4).expect("0.4 is valid.");
+            assert_eq!(State::new().brightness(level).brightness, Some(0.4));
+        }
+        #[test]
         fn builder() {
             let state = State::new()
                 .power(true)
@@ -1064,11 +1904,181 @@ mod tests {
                 assert_eq!(change.brightness, Some(0.1));
                 assert_eq!(change.kelvin, Some(500));
             }
+            #[test]
+            fn apply() {
+                use crate::http::light::{Group, LightColor, Location};
+                let light = Light {
+                    id: "d3b2f2d97452".to_string(),
+                    uuid: "8fa5f072-af97-44ed-ae54-e70fd7bd9d20".to_string(),
+                    label: "Lamp".to_string(),
+                    connected: true,
+                    power: Power(false),
+                    color: LightColor {
+                        hue: 350.0,
+                        saturation: 0.9,
+                        kelvin: 8000,
+                    },
+                    brightness: 0.7,
+                    group: Group {
+                        id: "1".to_string(),
+                        name: "Lounge".to_string(),
+                    },
+                    location: Location {
+                        id: "2".to_string(),
+                        name: "Home".to_string(),
+                    },
+                    product: None,
+                    infrared: None,
+                    last_seen: None,
+                    seconds_since_seen: None,
+                };
+                let state = StateChange::new()
+                    .hue(20)
+                    .saturation(0.3)
+                    .brightness(-1.0)
+                    .kelvin(2000)
+                    .apply(&light);
+                assert_eq!(state.power, Some(Power(false)));
+                assert_eq!(state.brightness, Some(0.0));
+                assert_eq!(state.infrared, None);
+                assert_eq!(
+                    state.color,
+                    Some(Color::Hsbk(Some(10), Some(1.0), None, Some(9000)))
+                );
+                let state = StateChange::new()
+                    .power(true)
+                    .hue(-350)
+                    .kelvin(-10_000)
+                    .apply(&light);
+                assert_eq!(state.power, Some(Power(true)));
+                assert_eq!(state.brightness, Some(0.7));
+                assert_eq!(
+                    state.color,
+                    Some(Color::Hsbk(Some(0), Some(0.9), None, Some(1500)))
+                );
+                let change = StateChange {
+                    infrared: Some(0.4),
+                    ..StateChange::new()
+                };
+                assert_eq!(change.apply(&light).infrared, Some(0.4));
+                let light = Light {
+                    infrared: Some(0.8),
+                    ..light
+                };
+                assert_eq!(change.apply(&light).infrared, Some(1.0));
+                assert_eq!(StateChange::new().apply(&light).infrared, Some(0.8));
+            }
+            #[test]
+            fn add() {
+                let first = StateChange::new()
+                    .power(false)
+                    .transition(::std::time::Duration::from_secs(3))
+                    .hue(i16::MAX)
+                    .kelvin(-200)
+                    .brightness(0.25);
+                let second = StateChange::new()
+                    .power(true)
+                    .hue(1)
+                    .kelvin(100)
+                    .brightness(0.25)
+                    .saturation(0.5);
+                let change = first + second;
+                assert_eq!(change.power, Some(Power(true)));
+                assert_eq!(change.duration.map(|d| d.0.as_secs()), Some(3));
+                assert_eq!(change.hue, Some(i16::MAX));
+                assert_eq!(change.kelvin, Some(-100));
+                assert_eq!(change.brightness, Some(0.5));
+                assert_eq!(change.saturation, Some(0.5));
+                assert_eq!(change.infrared, None);
+            }
+        }
+        #[test]
+        fn duration() {
+            let state: State =
+                serde_json::from_str(r#"{ "duration": 2.25 }"#).expect("Failed to parse state.");
+            let duration = state.duration.expect("Missing duration.");
+            assert_eq!(duration.inner(), ::std::time::Duration::from_millis(2250));
+            assert!((duration.as_secs_f64() - 2.25).abs() < f64::EPSILON);
         }
     }
     mod color {
         use super::*;
         #[test]
+        fn ordering() {
+            let nan = f32::NAN;
+            assert_eq!(Color::Saturation(nan), Color::Saturation(nan));
+            assert!(Color::Saturation(nan) > Color::Saturation(1.0));
+            assert_eq!(Color::Brightness(0.0), Color::Brightness(-0.0));
+            assert!(Color::White < Color::Hue(0));
+            assert!(
+                Color::Hsbk(None, Some(0.5), None, None) < Color::Hsbk(Some(0), None, None, None)
+            );
+            assert!(
+                Color::Hsbk(Some(1), None, None, None)
+                    < Color::Hsbk(Some(1), Some(0.0), None, None)
+            );
+            assert!(Color::Rgb([1, 0, 0]) > Color::Rgb([0, 255, 255]));
+            assert!(Color::Rgb([255, 255, 255]) < Color::RgbStr("#000000".to_string()));
+        }
+        #[test]
+        fn nearest_named() {
+            let nearest = |color: Color| color.nearest_named().map(|(name, _)| name);
+            assert_eq!(nearest(Color::Red), Some(NamedColor::Red));
+            assert_eq!(
+                Color::Purple.nearest_named().map(|(_, distance)| distance),
+                Some(0.0)
+            );
+            assert_eq!(nearest(Color::Rgb([250, 10, 10])), Some(NamedColor::Red));
+            assert_eq!(nearest(Color::Rgb([255, 255, 0])), Some(NamedColor::Yellow));
+            assert_eq!(nearest(Color::Rgb([0, 0, 255])), Some(NamedColor::Blue));
+            assert_eq!(
+                nearest(Color::Rgb([240, 240, 240])),
+                Some(NamedColor::White)
+            );
+            assert_eq!(
+                nearest(Color::RgbStr("#00ff00".to_string())),
+                Some(NamedColor::Green)
+            );
+            assert_eq!(nearest(Color::Hue(350)), Some(NamedColor::Red));
+            assert_eq!(
+                nearest(Color::Hsbk(Some(320), Some(0.9), None, None)),
+                Some(NamedColor::Pink)
+            );
+            assert_eq!(
+                nearest(Color::Hsbk(Some(320), Some(0.05), None, None)),
+                Some(NamedColor::White)
+            );
+            assert_eq!(nearest(Color::Custom("cyan".to_string())), None);
+            assert_eq!(nearest(Color::RgbStr("#zzzzzz".to_string())), None);
+        }
+        #[test]
+        fn mired() {
+            assert_eq!(Color::kelvin_to_mired(1502), 666);
+            assert_eq!(Color::from_mired(666), Ok(Color::Kelvin(1502)));
+            assert_eq!(Color::kelvin_to_mired(8929), 112);
+            assert_eq!(Color::from_mired(112), Ok(Color::Kelvin(8929)));
+            assert_eq!(Color::from_mired(667), Err(Error::KelvinLow(1499)));
+            assert_eq!(Color::from_mired(111), Err(Error::KelvinHigh(9009)));
+            assert_eq!(Color::from_mired(0), Err(Error::KelvinHigh(u16::MAX)));
+            assert_eq!(Color::kelvin_to_mired(0), u16::MAX);
+            assert_eq!(Color::kelvin_to_mired(1), u16::MAX);
+            for kelvin in (Kelvin::MIN + 2..=Kelvin::MAX).step_by(250) {
+                let mired = Color::kelvin_to_mired(kelvin);
+                match Color::from_mired(mired) {
+                    Ok(Color::Kelvin(k)) => assert!((i32::from(k) - i32::from(kelvin)).abs() < 50),
+                    other => panic!("Unexpected conversion: {:?}", other),
+                }
+            }
+        }
+        #[test]
+        fn hue_fraction() {
+            assert_eq!(Color::hue_fraction(0.0), Ok(Color::Hue(0)));
+            assert_eq!(Color::hue_fraction(0.5), Ok(Color::Hue(180)));
+            assert_eq!(Color::hue_fraction(1.0), Ok(Color::Hue(360)));
+            assert_eq!(Color::hue_fraction(1.01), Err(Error::HueFraction(1.01)));
+            assert!(Color::hue_fraction(f32::NAN).is_err());
+        }
+        #[test]
         fn serialize() {
             let color = Color::Red;
             assert_eq!(&format!("{}", color), "red");
@@ -1107,9 +2117,27 @@ mod tests {
             assert_eq!(&format!("{}", color), "#123456");
             let color = Color::RgbStr("#000000".to_string());
             assert_eq!(&format!("{}", color), "#000000");
+            let color = Color::hsbk(240, 0.531, 0.3, 3500);
+            assert_eq!(format!("{}", color).parse(), Ok(color));
         }
         #[test]
         fn deserialize() {
+            let color = "brightness:50%".parse();
+            assert_eq!(color, Ok(Color::Brightness(0.5)));
+            let color = "brightness:0.5".parse();
+            assert_eq!(color, Ok(Color::Brightness(0.5)));
+            let color = "brightness:150%".parse::<Color>();
+            assert_eq!(color, Err(ColorParseError::PercentageOutOfRange(150.0)));
+            let color = "saturation:75% hue:120".parse();
+            assert_eq!(color, Ok(Color::Hsbk(Some(120), Some(0.75), None, None)));
+            let color = "saturation:x%".parse::<Color>();
+            assert!(color.is_err());
+            let color = "#ff0000ff".parse();
+            assert_eq!(color, Ok(Color::Rgb([255, 0, 0])));
+            let color = "00ff00cc".parse();
+            assert_eq!(color, Ok(Color::Rgb([0, 255, 0])));
+            let color = "#ff0000fg".parse::<Color>();
+            assert_eq!(color, Err(ColorParseError::LongString));
             let color = "red".parse();
             assert_eq!(color, Ok(Color::Red));
             let color = "orange".parse();
@@ -1128,6 +2156,10 @@ mod tests {
             assert_eq!(color, Ok(Color::White));
             let color = "cyan".parse::<Color>();
             assert!(color.is_err());
+            let color = "#12z456".parse::<Color>();
+            assert_eq!(color, Err(ColorParseError::NonHexString));
+            let color = "zzzzzz".parse::<Color>();
+            assert_eq!(color, Err(ColorParseError::NonHexString));
             let color = "hue:240".parse();
             assert_eq!(color, Ok(Color::Hue(240)));
             let color = "saturation:0.531".parse();
@@ -1167,6 +2199,12 @@ mod tests {
             assert_eq!(color, Ok(Color::RgbStr("#123456".to_string())));
             let color = "#000000".parse();
             assert_eq!(color, Ok(Color::RgbStr("#000000".to_string())));
+            let color = "#abc".parse();
+            assert_eq!(color, Ok(Color::RgbStr("#aabbcc".to_string())));
+            let color = "abc".parse();
+            assert_eq!(color, Ok(Color::RgbStr("aabbcc".to_string())));
+            let color = "#abz".parse::<Color>();
+            assert_eq!(color, Err(ColorParseError::NonHexString));
         }
         #[test]
         fn validate() {
@@ -1252,6 +2290,25 @@ mod tests {
             assert!(color.validate().is_ok());
             let color = Color::RgbStr("#123456".to_string());
             assert!(color.validate().is_ok());
+            let color = Color::RgbStr("#abc".to_string());
+            assert!(color.validate().is_ok());
+            let color = Color::RgbStr("abc".to_string());
+            assert!(color.validate().is_ok());
+            let color = Color::RgbStr("#abz".to_string());
+            assert_eq!(
+                color.validate(),
+                Err(Error::RgbStrInvalidChar(true, "#abz".to_string()))
+            );
+            let color = Color::RgbStr("#12z456".to_string());
+            assert_eq!(
+                color.validate(),
+                Err(Error::RgbStrInvalidChar(true, "#12z456".to_string()))
+            );
+            let color = Color::RgbStr("zzzzzz".to_string());
+            assert_eq!(
+                color.validate(),
+                Err(Error::RgbStrInvalidChar(false, "zzzzzz".to_string()))
+            );
             let color = Color::RgbStr("1234567".to_string());
             assert_eq!(
                 color.validate(),
@@ -1263,5 +2320,24 @@ mod tests {
                 Err(Error::RgbStrLong(true, "#1234567".to_string()))
             );
         }
+        #[test]
+        fn approx_eq() {
+            let color = Color::Brightness(0.5);
+            assert!(color.approx_eq(&Color::Brightness(0.501), 0.01));
+            assert!(!color.approx_eq(&Color::Brightness(0.52), 0.01));
+            assert!(!color.approx_eq(&Color::Saturation(0.5), 0.01));
+            let color = Color::Hsbk(Some(240), Some(0.3), Some(0.8), Some(3500));
+            let other = Color::Hsbk(Some(240), Some(0.299_99), Some(0.800_01), Some(3500));
+            assert!(color.approx_eq(&other, 0.001));
+            let other = Color::Hsbk(Some(240), Some(0.3), None, Some(3500));
+            assert!(!color.approx_eq(&other, 0.001));
+            let other = Color::Hsbk(Some(240), Some(0.3), Some(0.8), Some(3501));
+            assert!(!color.approx_eq(&other, 0.001));
+            let color = Color::Kelvin(3500);
+            assert!(color.approx_eq(&Color::Kelvin(3500), 0.0));
+            assert!(!color.approx_eq(&Color::Kelvin(3501), 100.0));
+            let color = Color::RgbStr("#ff0000".to_string());
+            assert!(color.approx_eq(&Color::RgbStr("#ff0000".to_string()), 0.0));
+        }
     }
 }